@@ -1,8 +1,6 @@
-use futures::{SinkExt, StreamExt};
 use tokio_serial::SerialStream;
-use tokio_util::codec::Framed;
 
-use modbus_client::{Request, Response};
+use modbus_client::{codec::RtuCodec, Client};
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -13,45 +11,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let serial_builder = tokio_serial::new(tty_path, rate);
     let port = SerialStream::open(&serial_builder).unwrap();
 
-    let rq =
-        Request::read_multiple_holding_registers_request(slave, 0, 2);
-    let mut transport = Framed::new(port, rq);
+    let mut client = Client::new(port, RtuCodec::default());
 
-    transport.send(()).await?;
     // 01 03 04 03 10 00 D7 BB EC
-    while let Some(response) = transport.next().await {
-        match response {
-            Ok(response) => {
-                // println!("Response:\t{:?}", response);
-                match response {
-                    Response::ReadMultipleHoldingRegisters(
-                        _,
-                        _,
-                        res
-                    ) => {
-                        let res = res.unwrap();
-                        let a = res.get_values();
-                        println!(
-                            "{:?} {}",
-                            a,
-                            u16::from_be_bytes([
-                                *(a.get(2).unwrap()),
-                                *(a.get(3).unwrap())
-                            ]) as f32
-                                / 10.0
-                        );
-                        return Ok(());
-                    },
-                    _ => {
-                        println!("unknown")
-                    }
-                }
-            },
-            Err(e) => {
-                return Err(e.into());
-            }
-        }
-    }
+    let registers =
+        client.read_holding_registers(slave, 0, 2).await?;
+    println!("{:?} {}", registers, registers[1] as f32 / 10.0);
 
     Ok(())
 }
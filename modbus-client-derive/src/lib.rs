@@ -0,0 +1,258 @@
+//! `#[derive(ModbusRegisters)]`, see `modbus_client::ModbusRegisters`
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields,
+    Ident, Lit, LitInt, LitStr, Result, Type
+};
+
+#[proc_macro_derive(ModbusRegisters, attributes(modbus))]
+pub fn derive_modbus_registers(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Options of a `#[modbus(...)]` attribute
+#[derive(Default)]
+struct Options {
+    offset:     Option<usize>,
+    ty:         Option<Type>,
+    word_order: Option<TokenStream2>,
+    scale:      Option<f64>
+}
+
+impl Options {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut options = Options::default();
+        for attr in
+            attrs.iter().filter(|a| a.path().is_ident("modbus"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("offset") {
+                    let offset: LitInt = meta.value()?.parse()?;
+                    options.offset = Some(offset.base10_parse()?);
+                } else if meta.path.is_ident("ty") {
+                    let ty: LitStr = meta.value()?.parse()?;
+                    options.ty = Some(ty.parse()?);
+                } else if meta.path.is_ident("word_order") {
+                    let order: LitStr = meta.value()?.parse()?;
+                    options.word_order = Some(word_order(&order)?);
+                } else if meta.path.is_ident("scale") {
+                    let lit: Lit = meta.value()?.parse()?;
+                    let scale: f64 = match &lit {
+                        Lit::Float(f) => f.base10_parse()?,
+                        Lit::Int(i) => i.base10_parse()?,
+                        _ => {
+                            return Err(Error::new_spanned(
+                                lit,
+                                "expected a number"
+                            ))
+                        },
+                    };
+                    // encoding divides by the scale
+                    if scale == 0.0 || !scale.is_finite() {
+                        return Err(Error::new_spanned(
+                            lit,
+                            "scale must be a finite, nonzero number"
+                        ));
+                    }
+                    options.scale = Some(scale);
+                } else {
+                    return Err(meta.error("unknown modbus option"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+fn word_order(order: &LitStr) -> Result<TokenStream2> {
+    let variant = match order.value().to_uppercase().as_str() {
+        "ABCD" => quote!(Abcd),
+        "CDAB" => quote!(Cdab),
+        "BADC" => quote!(Badc),
+        "DCBA" => quote!(Dcba),
+        _ => {
+            return Err(Error::new_spanned(
+                order,
+                "expected ABCD, CDAB, BADC or DCBA"
+            ))
+        },
+    };
+    Ok(quote!(::modbus_client::WordOrder::#variant))
+}
+
+/// Whether `ty` is `f32` or `f64`, which aren't rounded when scaled
+fn is_float(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path)
+        if path.path.is_ident("f32") || path.path.is_ident("f64"))
+}
+
+/// Registers of the `RegisterValue` primitives, `None` for other
+/// types whose size is only known once the trait is resolved
+fn registers(ty: &Type) -> Option<usize> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    match path.path.get_ident()?.to_string().as_str() {
+        "u16" | "i16" => Some(1),
+        "u32" | "i32" | "f32" => Some(2),
+        "u64" | "i64" | "f64" => Some(4),
+        _ => None
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ModbusRegisters can only be derived for structs"
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "ModbusRegisters requires named fields"
+        ));
+    };
+    let options = Options::parse(&input.attrs)?;
+    if options.offset.is_some()
+        || options.ty.is_some()
+        || options.scale.is_some()
+    {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "only word_order is allowed on the struct"
+        ));
+    }
+    let default_order = options
+        .word_order
+        .unwrap_or_else(|| quote!(::modbus_client::WordOrder::Abcd));
+
+    let mut layout = Vec::new();
+    let mut decode = Vec::new();
+    let mut encode = Vec::new();
+    // registers of the fields placed so far and where the next field
+    // starts, as far as they are known here
+    let mut placed: Vec<(usize, usize, &Ident)> = Vec::new();
+    let mut next = Some(0usize);
+    for field in &fields.named {
+        let ident = &field.ident;
+        let options = Options::parse(&field.attrs)?;
+        let field_ty = &field.ty;
+        let raw_ty = options.ty.as_ref().unwrap_or(field_ty);
+        let order =
+            options.word_order.as_ref().unwrap_or(&default_order);
+        let first = options.offset.or(next);
+        let end = first.zip(registers(raw_ty)).map(|(a, b)| a + b);
+        if let (Some(first), Some(end), Some(name)) =
+            (first, end, ident.as_ref())
+        {
+            if let Some((_, _, other)) =
+                placed.iter().find(|(start, stop, _)| {
+                    first < *stop && *start < end
+                })
+            {
+                return Err(Error::new_spanned(
+                    name,
+                    format!("registers overlap with field `{other}`")
+                ));
+            }
+            placed.push((first, end, name));
+        }
+        next = end;
+        let start = match options.offset {
+            Some(offset) => quote!(#offset),
+            None => quote!(offset)
+        };
+        let size = quote! {
+            <#raw_ty as ::modbus_client::RegisterValue>::REGISTERS
+        };
+        // each field starts at its offset or after the previous one
+        let step = quote! {
+            let start: usize = #start;
+            offset = start + #size;
+        };
+        layout.push(quote! {
+            #step
+            if offset > len {
+                len = offset;
+            }
+        });
+        let raw = quote! {
+            <#raw_ty as ::modbus_client::RegisterValue>::from_registers(
+                registers.get(start..offset)?,
+                #order
+            )?
+        };
+        let (value, raw_value) = match options.scale {
+            Some(scale) => {
+                let raw_value = if is_float(raw_ty) {
+                    quote!((self.#ident as f64 / #scale) as #raw_ty)
+                } else {
+                    quote! {
+                        (self.#ident as f64 / #scale).round() as #raw_ty
+                    }
+                };
+                (
+                    quote!((#raw as f64 * #scale) as #field_ty),
+                    raw_value
+                )
+            },
+            None => (raw, quote!(self.#ident))
+        };
+        decode.push(quote! {
+            #ident: {
+                #step
+                #value
+            }
+        });
+        encode.push(quote! {
+            #step
+            registers[start..offset].copy_from_slice(
+                &::modbus_client::RegisterValue::to_registers(
+                    &(#raw_value),
+                    #order
+                )
+            );
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    Ok(quote! {
+        #[allow(unused_assignments, clippy::unnecessary_cast)]
+        impl #impl_generics ::modbus_client::ModbusRegisters
+            for #name #ty_generics #where_clause
+        {
+            const REGISTERS: u16 = {
+                let mut offset = 0usize;
+                let mut len = 0usize;
+                #(#layout)*
+                len as u16
+            };
+
+            fn from_registers(registers: &[u16]) -> Option<Self> {
+                let mut offset = 0usize;
+                Some(Self {
+                    #(#decode,)*
+                })
+            }
+
+            fn to_registers(&self) -> Vec<u16> {
+                let len = <Self as ::modbus_client::ModbusRegisters>
+                    ::REGISTERS as usize;
+                let mut registers = vec![0u16; len];
+                let mut offset = 0usize;
+                #(#encode)*
+                registers
+            }
+        }
+    })
+}
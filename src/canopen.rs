@@ -0,0 +1,186 @@
+//! CANopen general reference (MEI type 0x0D) and the SDO transfers
+//! tunneled through it
+//!
+//! The gateway forwards the 8 bytes of a CANopen SDO frame to the
+//! node addressed by the unit id. Only expedited transfers, i.e. at
+//! most 4 data bytes, are built by the helpers.
+
+use std::{fmt, sync::Arc};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::{CustomFunction, Error};
+
+/// MEI type of CANopen general reference
+pub const MEI_CANOPEN_GENERAL_REFERENCE: u8 = 0x0D;
+
+/// CANopen general reference request body (Function Code: 0x2B,
+/// MEI type: 0x0D)
+///
+/// The payload is opaque, `response_len` computes the length of the
+/// response payload.
+#[derive(Clone)]
+pub struct CanopenGeneralReferenceRequest {
+    pub payload:      Vec<u8>,
+    pub response_len: Arc<dyn CustomFunction>
+}
+
+impl CanopenGeneralReferenceRequest {
+    pub fn new(
+        payload: Vec<u8>,
+        response_len: Arc<dyn CustomFunction>
+    ) -> Self {
+        Self {
+            payload,
+            response_len
+        }
+    }
+}
+
+impl fmt::Debug for CanopenGeneralReferenceRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CanopenGeneralReferenceRequest")
+            .field("payload", &self.payload)
+            .finish()
+    }
+}
+
+impl From<CanopenGeneralReferenceRequest> for BytesMut {
+    fn from(request: CanopenGeneralReferenceRequest) -> Self {
+        let mut buf =
+            BytesMut::with_capacity(1 + request.payload.len());
+        buf.put_u8(MEI_CANOPEN_GENERAL_REFERENCE);
+        buf.put_slice(&request.payload);
+        buf
+    }
+}
+
+/// CANopen general reference response body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanopenGeneralReferenceResponse {
+    pub mei_type: u8,
+    pub payload:  Vec<u8>
+}
+
+impl From<Bytes> for CanopenGeneralReferenceResponse {
+    fn from(mut src: Bytes) -> Self {
+        Self {
+            mei_type: src.get_u8(),
+            payload:  src.to_vec()
+        }
+    }
+}
+
+/// Length of a SDO frame
+pub const SDO_LEN: usize = 8;
+
+/// Response length of SDO requests
+pub(crate) fn sdo_response_len(_: &[u8], _: &[u8]) -> Option<usize> {
+    Some(SDO_LEN)
+}
+
+/// Whether `payload` initiates an SDO upload, which only reads the
+/// object
+pub(crate) fn is_sdo_upload(payload: &[u8]) -> bool {
+    payload.len() == SDO_LEN && payload[0] >> 5 == 0x02
+}
+
+/// Expedited SDO upload (read) of object `index`/`subindex`
+pub fn sdo_upload(index: u16, subindex: u8) -> Vec<u8> {
+    sdo(0x40, index, subindex, &[])
+}
+
+/// Expedited SDO download (write) of 1 to 4 bytes to object
+/// `index`/`subindex`, `None` for more data
+pub fn sdo_download(
+    index: u16,
+    subindex: u8,
+    data: &[u8]
+) -> Option<Vec<u8>> {
+    if data.is_empty() || data.len() > 4 {
+        return None;
+    }
+    // expedited, size indicated, n unused bytes
+    let command = 0x23 | ((4 - data.len() as u8) << 2);
+    Some(sdo(command, index, subindex, data))
+}
+
+fn sdo(
+    command: u8,
+    index: u16,
+    subindex: u8,
+    data: &[u8]
+) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(SDO_LEN);
+    frame.push(command);
+    frame.extend_from_slice(&index.to_le_bytes());
+    frame.push(subindex);
+    frame.extend_from_slice(data);
+    frame.resize(SDO_LEN, 0);
+    frame
+}
+
+/// Answer of a CANopen node to an SDO request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SdoResponse {
+    /// Data of an expedited upload
+    Upload(Vec<u8>),
+    /// Confirmation of a download
+    Download,
+    /// Transfer aborted with an abort code
+    Abort(u32)
+}
+
+impl SdoResponse {
+    /// Parse an SDO frame, `None` if it isn't a valid answer to an
+    /// expedited transfer of object `index`/`subindex`
+    pub fn parse(
+        frame: &[u8],
+        index: u16,
+        subindex: u8
+    ) -> Option<Self> {
+        if frame.len() != SDO_LEN
+            || frame[1..3] != index.to_le_bytes()
+            || frame[3] != subindex
+        {
+            return None;
+        }
+        let command = frame[0];
+        let data = &frame[4..];
+        let response = match command >> 5 {
+            // upload response, expedited
+            0x02 if command & 0x02 != 0 => {
+                let len = if command & 0x01 != 0 {
+                    4 - ((command >> 2) & 0x03) as usize
+                } else {
+                    4
+                };
+                SdoResponse::Upload(data[..len].to_vec())
+            },
+            0x03 => SdoResponse::Download,
+            0x04 => SdoResponse::Abort(u32::from_le_bytes([
+                data[0], data[1], data[2], data[3]
+            ])),
+            _ => return None
+        };
+        Some(response)
+    }
+
+    /// Data of an upload, `Error::SdoAbort` for aborted transfers
+    pub(crate) fn into_upload(self) -> Result<Vec<u8>, Error> {
+        match self {
+            SdoResponse::Upload(data) => Ok(data),
+            SdoResponse::Abort(code) => Err(Error::SdoAbort(code)),
+            SdoResponse::Download => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// `Error::SdoAbort` for aborted downloads
+    pub(crate) fn into_download(self) -> Result<(), Error> {
+        match self {
+            SdoResponse::Download => Ok(()),
+            SdoResponse::Abort(code) => Err(Error::SdoAbort(code)),
+            SdoResponse::Upload(_) => Err(Error::UnexpectedResponse)
+        }
+    }
+}
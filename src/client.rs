@@ -0,0 +1,2290 @@
+use std::{
+    io::{self, ErrorKind},
+    time::Duration
+};
+
+use bytes::BytesMut;
+
+use futures::{future::BoxFuture, SinkExt, StreamExt};
+use log::warn;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite},
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+    time::Instant
+};
+use tokio_util::codec::Framed;
+
+#[cfg(feature = "tls")]
+use crate::transport::tls;
+use crate::{
+    codec::{ClientCodec, RtuCodec, TcpCodec},
+    data,
+    error::Result,
+    frame::records_in_file,
+    transport::{Connect, UdpStream},
+    util::{
+        bits_to_bytes, bytes_to_bits, bytes_to_registers,
+        registers_to_bytes
+    },
+    CustomFunction, DeviceIdentification, DiagnosticsSubFunction,
+    Error, FileRecord, FileSubRequest, GetCommEventCounterResponse,
+    GetCommEventLogResponse, Head, ModbusRegisters, ReadDeviceIdCode,
+    ReadFifoQueueRequest, ReadFileRecordRequest, RegisterMap,
+    RegisterValue, Request, Response, RetryPolicy, SdoResponse,
+    Version, WordOrder, WriteFileRecordRequest,
+    MEI_CANOPEN_GENERAL_REFERENCE
+};
+
+/// Async modbus client
+///
+/// Owns a transport (serial port, tcp stream, ...) and a codec which
+/// selects the framing, and sends one request at a time over it.
+///
+/// Responses are awaited for at most `DEFAULT_TIMEOUT`, see
+/// `set_timeout` and `call_with_timeout`. A timed out call fails with
+/// `Error::Timeout`, an exception response with `Error::Exception`.
+/// Failed transactions are retried according to the `RetryPolicy`,
+/// see `set_retry_policy`.
+///
+/// Clients created with `connect` reconnect before the next request
+/// once the connection broke. RTU/ASCII over a socket also reconnect
+/// after a timeout, so a late response can't be taken as the answer
+/// to the next request.
+///
+/// Writes to unit id 0 over RTU/ASCII are broadcasts, which aren't
+/// answered, see `broadcast`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use modbus_client::{codec::TcpCodec, Client};
+/// use tokio::net::TcpStream;
+///
+/// # async fn run() -> Result<(), modbus_client::Error> {
+/// let stream = TcpStream::connect("127.0.0.1:502").await?;
+/// let mut client = Client::new(stream, TcpCodec::default());
+/// let registers = client.read_holding_registers(0x01, 0x00, 2).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Client<T, C> {
+    transport:  Framed<T, C>,
+    timeout:    Option<Duration>,
+    retry:      RetryPolicy,
+    connector:  Option<Connector<T>>,
+    broken:     bool,
+    datagram:   bool,
+    turnaround: Duration,
+    idle_at:    Option<Instant>
+}
+
+type Connector<T> =
+    Box<dyn Fn() -> BoxFuture<'static, io::Result<T>> + Send + Sync>;
+
+/// Modbus TCP client
+pub type TcpClient = Client<TcpStream, TcpCodec>;
+/// RTU frames over a TCP socket, e.g. to a serial-to-Ethernet
+/// converter
+pub type RtuOverTcpClient = Client<TcpStream, RtuCodec>;
+/// Modbus UDP client, each datagram carries one MBAP frame
+pub type UdpClient = Client<UdpStream, TcpCodec>;
+/// RTU frames over UDP datagrams
+pub type RtuOverUdpClient = Client<UdpStream, RtuCodec>;
+/// Modbus/TCP Security client, MBAP frames over TLS
+#[cfg(feature = "tls")]
+pub type TlsClient = Client<tls::TlsStream<TcpStream>, TcpCodec>;
+
+impl<T, C> Client<T, C>
+where
+    T: Connect + AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    C: ClientCodec + Default
+{
+    /// Connect a socket transport to `addr`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::RtuOverTcpClient;
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = RtuOverTcpClient::connect("127.0.0.1:4001").await?;
+    /// let registers = client.read_holding_registers(0x01, 0x00, 2).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self> {
+        let addr =
+            lookup_host(addr).await?.next().ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "No socket address"
+                )
+            })?;
+        let connector: Connector<T> =
+            Box::new(move || Box::pin(T::connect(addr)));
+        Self::with_connector(connector, T::DATAGRAM).await
+    }
+}
+
+#[cfg(feature = "tls")]
+impl TlsClient {
+    /// Connect to a Modbus/TCP Security server at `addr`, usually on
+    /// port `tls::DEFAULT_PORT`
+    ///
+    /// Fails with `Error::Tls` if the server certificate isn't valid
+    /// for `config`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{transport::tls::TlsConfig, TlsClient};
+    ///
+    /// # async fn run(config: TlsConfig) -> Result<(), modbus_client::Error> {
+    /// let mut client =
+    ///     TlsClient::connect_tls("192.168.1.10:802", config).await?;
+    /// let role = client.peer_role()?;
+    /// let registers = client.read_holding_registers(0x01, 0x00, 2).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_tls(
+        addr: impl ToSocketAddrs,
+        config: tls::TlsConfig
+    ) -> Result<Self> {
+        let addr =
+            lookup_host(addr).await?.next().ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "No socket address"
+                )
+            })?;
+        let connector: Connector<tls::TlsStream<TcpStream>> =
+            Box::new(move || {
+                let config = config.clone();
+                Box::pin(async move { config.connect(addr).await })
+            });
+        Self::with_connector(connector, false).await
+    }
+
+    /// Modbus role of the server, see `tls::peer_role`
+    pub fn peer_role(&self) -> Result<Option<String>> {
+        tls::peer_role(self.transport.get_ref())
+    }
+}
+
+impl<T, C> Client<T, C>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    C: ClientCodec + Default
+{
+    async fn with_connector(
+        connector: Connector<T>,
+        datagram: bool
+    ) -> Result<Self> {
+        let timeout = Some(Self::DEFAULT_TIMEOUT);
+        let transport = with_timeout(timeout, connector()).await??;
+        let mut client = if datagram {
+            Self::datagram(transport, C::default())
+        } else {
+            Self::new(transport, C::default())
+        };
+        client.connector = Some(connector);
+        Ok(client)
+    }
+}
+
+impl<T, C> Client<T, C>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+    C: ClientCodec
+{
+    /// Default response timeout
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+    /// Default delay after a broadcast
+    pub const DEFAULT_TURNAROUND_DELAY: Duration =
+        Duration::from_millis(100);
+
+    /// Client over the byte stream `transport`, see `datagram` for
+    /// datagram transports
+    pub fn new(transport: T, codec: C) -> Self {
+        Self {
+            transport:  Framed::new(transport, codec),
+            timeout:    Some(Self::DEFAULT_TIMEOUT),
+            retry:      RetryPolicy::default(),
+            connector:  None,
+            broken:     false,
+            datagram:   false,
+            turnaround: Self::DEFAULT_TURNAROUND_DELAY,
+            idle_at:    None
+        }
+    }
+
+    /// Client over a datagram transport, e.g. a `UdpStream`, each
+    /// read of it returns one datagram
+    ///
+    /// Each datagram is decoded as one frame, the rest of it is
+    /// dropped, as are datagrams without a complete frame.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{codec::TcpCodec, transport::UdpStream, Client};
+    /// use tokio::net::UdpSocket;
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    /// socket.connect("127.0.0.1:502").await?;
+    /// let stream = UdpStream::from_socket(socket);
+    /// let mut client = Client::datagram(stream, TcpCodec::default());
+    /// let registers = client.read_holding_registers(0x01, 0x00, 2).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn datagram(transport: T, codec: C) -> Self {
+        Self {
+            datagram: true,
+            ..Self::new(transport, codec)
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        self.transport.get_ref()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.transport.into_inner()
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Set the default response timeout, `None` waits forever
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn turnaround_delay(&self) -> Duration {
+        self.turnaround
+    }
+
+    /// Set the delay between a broadcast and the next request, which
+    /// gives the servers time to process the broadcast (100 to 200
+    /// ms by the serial line specification)
+    pub fn set_turnaround_delay(&mut self, delay: Duration) {
+        self.turnaround = delay;
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Reconnect the transport, only clients created with `connect`
+    /// can reconnect
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let Some(connector) = self.connector.as_ref() else {
+            return Err(Error::Io(io::Error::new(
+                ErrorKind::Unsupported,
+                "Transport can't reconnect"
+            )));
+        };
+        let transport =
+            with_timeout(self.timeout, connector()).await??;
+        *self.transport.get_mut() = transport;
+        self.transport.read_buffer_mut().clear();
+        self.transport.write_buffer_mut().clear();
+        self.broken = false;
+        Ok(())
+    }
+
+    /// Send a request and wait for its response, at most for the
+    /// default timeout
+    pub async fn call(
+        &mut self,
+        request: Request
+    ) -> Result<Response> {
+        self.call_with_timeout(request, self.timeout).await
+    }
+
+    /// Send a request and wait for its response, at most for
+    /// `timeout`
+    ///
+    /// On timeout a partial RTU/ASCII frame is discarded, so the next
+    /// request starts with an empty buffer. Retryable failures are
+    /// retried according to the retry policy.
+    ///
+    /// Broadcasts aren't answered and fail with
+    /// `Error::InvalidBroadcast`, see `broadcast`.
+    pub async fn call_with_timeout(
+        &mut self,
+        request: Request,
+        timeout: Option<Duration>
+    ) -> Result<Response> {
+        if self.is_broadcast(&request) {
+            return Err(Error::InvalidBroadcast);
+        }
+        let retry = self.retry.clone();
+        let mut attempt = 1;
+        loop {
+            let rs = self.transact(request.clone(), timeout).await;
+            let failure = match &rs {
+                Ok(response) => response
+                    .exception()
+                    .filter(|e| retry.is_retryable_exception(e))
+                    .map(|e| e.to_string()),
+                Err(e) => retry.is_retryable(e).then(|| e.to_string())
+            };
+            let Some(failure) = failure else {
+                return rs;
+            };
+            if attempt >= retry.max_attempts
+                || (request.is_write() && !retry.retry_writes)
+            {
+                return rs;
+            }
+            warn!("Attempt {} failed: {}, retry", attempt, failure);
+            tokio::time::sleep(retry.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn transact(
+        &mut self,
+        request: Request,
+        timeout: Option<Duration>
+    ) -> Result<Response> {
+        self.wait_turnaround().await;
+        if self.broken {
+            self.reconnect().await?;
+        }
+        let rs = self.exchange(request, timeout).await;
+        if let Err(e) = &rs {
+            let rtu =
+                self.transport.codec().version() != Version::Tcp;
+            self.broken = self.connector.is_some()
+                && (is_fatal(e)
+                    || (rtu && matches!(e, Error::Timeout)));
+        }
+        rs
+    }
+
+    async fn exchange(
+        &mut self,
+        request: Request,
+        timeout: Option<Duration>
+    ) -> Result<Response> {
+        self.transport.send(request).await?;
+        match with_timeout(timeout, self.receive()).await {
+            Ok(response) => response,
+            Err(e) => {
+                self.discard();
+                Err(e)
+            }
+        }
+    }
+
+    /// Send a write request to all servers of a serial line (unit id
+    /// 0), the unit id of `request` is replaced
+    ///
+    /// None of the servers answers, the next request is delayed by
+    /// the turnaround delay. Reads, including Read/Write Multiple
+    /// registers, and modbus TCP requests fail with
+    /// `Error::InvalidBroadcast`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{Request, RtuOverTcpClient};
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = RtuOverTcpClient::connect("127.0.0.1:4001").await?;
+    /// let request =
+    ///     Request::write_single_holding_register_request(0x00, 0x10, 1);
+    /// client.broadcast(request).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn broadcast(
+        &mut self,
+        mut request: Request
+    ) -> Result<()> {
+        // FC 0x17 writes, but answers with the registers it read
+        if !request.is_write()
+            || matches!(
+                request,
+                Request::ReadWriteMultipleRegisters(..)
+            )
+            || self.transport.codec().version() == Version::Tcp
+        {
+            return Err(Error::InvalidBroadcast);
+        }
+        request.head_mut().uid = Head::BROADCAST_UID;
+        self.send(request).await?;
+        self.idle_at = Some(Instant::now() + self.turnaround);
+        Ok(())
+    }
+
+    /// Whether `request` is a broadcast, unit id 0 addresses a
+    /// single server over modbus TCP
+    fn is_broadcast(&self, request: &Request) -> bool {
+        request.is_broadcast()
+            && self.transport.codec().version() != Version::Tcp
+    }
+
+    /// Call a write request, `None` for broadcasts
+    async fn call_write(
+        &mut self,
+        request: Request
+    ) -> Result<Option<Response>> {
+        if self.is_broadcast(&request) {
+            self.broadcast(request).await?;
+            return Ok(None);
+        }
+        self.call(request).await.map(Some)
+    }
+
+    /// Wait for the end of the turnaround delay of a broadcast
+    async fn wait_turnaround(&mut self) {
+        if let Some(idle_at) = self.idle_at.take() {
+            tokio::time::sleep_until(idle_at).await;
+        }
+    }
+
+    /// Send a request which the server doesn't answer
+    async fn send(&mut self, request: Request) -> Result<()> {
+        self.wait_turnaround().await;
+        if self.broken {
+            self.reconnect().await?;
+        }
+        let rs = self.transport.send(request).await;
+        if let Err(e) = &rs {
+            self.broken = self.connector.is_some() && is_fatal(e);
+        }
+        rs
+    }
+
+    async fn receive(&mut self) -> Result<Response> {
+        if self.datagram {
+            return self.receive_datagram().await;
+        }
+        match self.transport.next().await {
+            Some(Ok(response)) => Ok(response),
+            Some(Err(e)) => {
+                // the stream yields `None` once after an error
+                let _ = self.transport.next().await;
+                self.discard();
+                Err(e)
+            },
+            None => Err(Error::Io(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "Connection closed"
+            )))
+        }
+    }
+
+    /// Decode each datagram as one complete frame, the rest of a
+    /// datagram is dropped, as are datagrams without a frame (e.g.
+    /// truncated ones or replies to earlier requests)
+    async fn receive_datagram(&mut self) -> Result<Response> {
+        loop {
+            let mut datagram =
+                BytesMut::with_capacity(UdpStream::MAX_DATAGRAM);
+            let len = self
+                .transport
+                .get_mut()
+                .read_buf(&mut datagram)
+                .await?;
+            if len == 0 {
+                return Err(Error::Io(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "Connection closed"
+                )));
+            }
+            match self.transport.codec_mut().decode(&mut datagram)? {
+                Some(response) => {
+                    if !datagram.is_empty() {
+                        warn!(
+                            "Drop {} bytes after the frame",
+                            datagram.len()
+                        );
+                    }
+                    return Ok(response);
+                },
+                None => warn!("Drop datagram of {} bytes", len)
+            }
+        }
+    }
+
+    /// Discard a partial RTU/ASCII frame, frames on a TCP stream are
+    /// skipped by tid
+    fn discard(&mut self) {
+        if self.transport.codec().version() != Version::Tcp {
+            self.transport.read_buffer_mut().clear();
+        }
+    }
+
+    /// Read coils (Function Code: 0x01)
+    ///
+    /// More than `Request::MAX_READ_BITS` coils are read with several
+    /// requests, see `read_holding_registers`.
+    pub async fn read_coils(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<bool>> {
+        let chunks =
+            split(address, quantity, Request::MAX_READ_BITS)?;
+        let mut coils = Vec::with_capacity(quantity as usize);
+        for &(address, quantity) in &chunks {
+            let rs = self
+                .read_coils_chunk(unit_id, address, quantity)
+                .await;
+            coils.extend(chunk_result(rs, address, chunks.len())?);
+        }
+        Ok(coils)
+    }
+
+    async fn read_coils_chunk(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<bool>> {
+        let request =
+            Request::read_coils_request(unit_id, address, quantity);
+        match self.call(request).await? {
+            Response::ReadCoils(_, _, rs) => {
+                let coils =
+                    bytes_to_bits(&rs?.get_values(), quantity);
+                check_len(coils, quantity)
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read discrete inputs (Function Code: 0x02)
+    ///
+    /// More than `Request::MAX_READ_BITS` inputs are read with
+    /// several requests, see `read_holding_registers`.
+    pub async fn read_discrete_inputs(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<bool>> {
+        let chunks =
+            split(address, quantity, Request::MAX_READ_BITS)?;
+        let mut inputs = Vec::with_capacity(quantity as usize);
+        for &(address, quantity) in &chunks {
+            let rs = self
+                .read_discrete_inputs_chunk(
+                    unit_id, address, quantity
+                )
+                .await;
+            inputs.extend(chunk_result(rs, address, chunks.len())?);
+        }
+        Ok(inputs)
+    }
+
+    async fn read_discrete_inputs_chunk(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<bool>> {
+        let request = Request::read_discrete_request(
+            unit_id, address, quantity
+        );
+        match self.call(request).await? {
+            Response::ReadDiscreteInputs(_, _, rs) => {
+                let inputs =
+                    bytes_to_bits(&rs?.get_values(), quantity);
+                check_len(inputs, quantity)
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read holding registers (Function Code: 0x03)
+    ///
+    /// More than `Request::MAX_READ_REGISTERS` registers are read
+    /// with several requests in address order. An exception response
+    /// to one of them fails with `Error::ChunkException`, which
+    /// carries the first address of the failed request.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::TcpClient;
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// // 4 requests: 125 + 125 + 125 + 125 registers
+    /// let registers = client.read_holding_registers(0x01, 0, 500).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_holding_registers(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<u16>> {
+        let chunks =
+            split(address, quantity, Request::MAX_READ_REGISTERS)?;
+        let mut registers = Vec::with_capacity(quantity as usize);
+        for &(address, quantity) in &chunks {
+            let rs = self
+                .read_holding_registers_chunk(
+                    unit_id, address, quantity
+                )
+                .await;
+            registers.extend(chunk_result(
+                rs,
+                address,
+                chunks.len()
+            )?);
+        }
+        Ok(registers)
+    }
+
+    async fn read_holding_registers_chunk(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<u16>> {
+        let request =
+            Request::read_multiple_holding_registers_request(
+                unit_id, address, quantity
+            );
+        match self.call(request).await? {
+            Response::ReadMultipleHoldingRegisters(_, _, rs) => {
+                check_len(
+                    bytes_to_registers(&rs?.get_values()),
+                    quantity
+                )
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read input registers (Function Code: 0x04)
+    ///
+    /// More than `Request::MAX_READ_REGISTERS` registers are read
+    /// with several requests, see `read_holding_registers`.
+    pub async fn read_input_registers(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<u16>> {
+        let chunks =
+            split(address, quantity, Request::MAX_READ_REGISTERS)?;
+        let mut registers = Vec::with_capacity(quantity as usize);
+        for &(address, quantity) in &chunks {
+            let rs = self
+                .read_input_registers_chunk(
+                    unit_id, address, quantity
+                )
+                .await;
+            registers.extend(chunk_result(
+                rs,
+                address,
+                chunks.len()
+            )?);
+        }
+        Ok(registers)
+    }
+
+    async fn read_input_registers_chunk(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        quantity: u16
+    ) -> Result<Vec<u16>> {
+        let request = Request::read_input_registers_request(
+            unit_id, address, quantity
+        );
+        match self.call(request).await? {
+            Response::ReadInputRegisters(_, _, rs) => check_len(
+                bytes_to_registers(&rs?.get_values()),
+                quantity
+            ),
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Write a single coil (Function Code: 0x05)
+    pub async fn write_single_coil(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        value: bool
+    ) -> Result<()> {
+        let value = if value { 0xFF00 } else { 0x0000 };
+        let request = Request::write_single_coil_request(
+            unit_id, address, value
+        );
+        let Some(rs) = self.call_write(request).await? else {
+            return Ok(());
+        };
+        match rs {
+            Response::WriteSingleCoil(_, _, rs) => rs.map(|_| ())?,
+            _ => return Err(Error::UnexpectedResponse)
+        }
+        Ok(())
+    }
+
+    /// Write a single holding register (Function Code: 0x06)
+    pub async fn write_single_register(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        value: u16
+    ) -> Result<()> {
+        let request = Request::write_single_holding_register_request(
+            unit_id, address, value
+        );
+        let Some(rs) = self.call_write(request).await? else {
+            return Ok(());
+        };
+        match rs {
+            Response::WriteSingleHoldingRegister(_, _, rs) => {
+                rs.map(|_| ())?
+            },
+            _ => return Err(Error::UnexpectedResponse)
+        }
+        Ok(())
+    }
+
+    /// Write multiple coils (Function Code: 0x0F)
+    ///
+    /// More than `Request::MAX_WRITE_BITS` coils are written with
+    /// several requests, see `write_multiple_registers`.
+    pub async fn write_multiple_coils(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        values: &[bool]
+    ) -> Result<()> {
+        let chunks =
+            split_values(address, values, Request::MAX_WRITE_BITS)?;
+        for &(address, values) in &chunks {
+            let rs = self
+                .write_multiple_coils_chunk(unit_id, address, values)
+                .await;
+            chunk_result(rs, address, chunks.len())?;
+        }
+        Ok(())
+    }
+
+    async fn write_multiple_coils_chunk(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        values: &[bool]
+    ) -> Result<()> {
+        let request = Request::write_multiple_coils_request(
+            unit_id,
+            address,
+            values.len() as u16,
+            bits_to_bytes(values)
+        );
+        let Some(rs) = self.call_write(request).await? else {
+            return Ok(());
+        };
+        match rs {
+            Response::WriteMultipleCoils(_, _, rs) => {
+                rs.map(|_| ())?
+            },
+            _ => return Err(Error::UnexpectedResponse)
+        }
+        Ok(())
+    }
+
+    /// Write multiple holding registers (Function Code: 0x10)
+    ///
+    /// More than `Request::MAX_WRITE_REGISTERS` registers are written
+    /// with several requests in address order, the requests after a
+    /// failed one aren't sent. An exception response to one of them
+    /// fails with `Error::ChunkException`, which carries the first
+    /// address of the failed request.
+    pub async fn write_multiple_registers(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        values: &[u16]
+    ) -> Result<()> {
+        let chunks = split_values(
+            address,
+            values,
+            Request::MAX_WRITE_REGISTERS
+        )?;
+        for &(address, values) in &chunks {
+            let rs = self
+                .write_multiple_registers_chunk(
+                    unit_id, address, values
+                )
+                .await;
+            chunk_result(rs, address, chunks.len())?;
+        }
+        Ok(())
+    }
+
+    async fn write_multiple_registers_chunk(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        values: &[u16]
+    ) -> Result<()> {
+        let request =
+            Request::write_multiple_holding_registers_request(
+                unit_id,
+                address,
+                registers_to_bytes(values)
+            );
+        let Some(rs) = self.call_write(request).await? else {
+            return Ok(());
+        };
+        match rs {
+            Response::WriteMultipleHoldingRegisters(_, _, rs) => {
+                rs.map(|_| ())?
+            },
+            _ => return Err(Error::UnexpectedResponse)
+        }
+        Ok(())
+    }
+
+    /// Read `count` values of type `V` from holding registers
+    /// (Function Code: 0x03), each spread across `V::REGISTERS`
+    /// registers in `order`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{TcpClient, WordOrder};
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let values = client
+    ///     .read_holding_values::<f32>(0x01, 0x00, 2, WordOrder::Cdab)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_holding_values<V: RegisterValue>(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        count: u16,
+        order: WordOrder
+    ) -> Result<Vec<V>> {
+        let quantity = values_quantity::<V>(count)?;
+        let registers = self
+            .read_holding_registers(unit_id, address, quantity)
+            .await?;
+        decode_values(&registers, count, order)
+    }
+
+    /// Read `count` values of type `V` from input registers
+    /// (Function Code: 0x04), each spread across `V::REGISTERS`
+    /// registers in `order`
+    pub async fn read_input_values<V: RegisterValue>(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        count: u16,
+        order: WordOrder
+    ) -> Result<Vec<V>> {
+        let quantity = values_quantity::<V>(count)?;
+        let registers = self
+            .read_input_registers(unit_id, address, quantity)
+            .await?;
+        decode_values(&registers, count, order)
+    }
+
+    /// Write values of type `V` to holding registers (Function Code:
+    /// 0x10), each spread across `V::REGISTERS` registers in `order`
+    pub async fn write_values<V: RegisterValue>(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        values: &[V],
+        order: WordOrder
+    ) -> Result<()> {
+        let registers = data::encode(values, order);
+        self.write_multiple_registers(unit_id, address, &registers)
+            .await
+    }
+
+    /// Read a block of holding registers at `address` into `B`
+    /// (Function Code: 0x03), see `ModbusRegisters`
+    ///
+    /// Blocks longer than `Request::MAX_READ_REGISTERS` are read like
+    /// `read_holding_registers` does.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "derive")]
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// use modbus_client::{ModbusRegisters, TcpClient};
+    ///
+    /// #[derive(ModbusRegisters)]
+    /// struct Setpoints {
+    ///     speed:  f32,
+    ///     torque: i16
+    /// }
+    ///
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let setpoints: Setpoints = client.read_block(0x01, 0x0100).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_block<B: ModbusRegisters>(
+        &mut self,
+        unit_id: u8,
+        address: u16
+    ) -> Result<B> {
+        let registers = self
+            .read_holding_registers(unit_id, address, B::REGISTERS)
+            .await?;
+        B::from_registers(&registers).ok_or(Error::UnexpectedResponse)
+    }
+
+    /// Write `block` to holding registers at `address` (Function
+    /// Code: 0x10), see `ModbusRegisters`
+    pub async fn write_block<B: ModbusRegisters>(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        block: &B
+    ) -> Result<()> {
+        self.write_multiple_registers(
+            unit_id,
+            address,
+            &block.to_registers()
+        )
+        .await
+    }
+
+    /// Read the point `name` of a register map, scaled into its
+    /// engineering unit
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{RegisterMap, TcpClient};
+    ///
+    /// # async fn run(map: RegisterMap) -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let power = client.read_point(0x01, &map, "active_power").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_point(
+        &mut self,
+        unit_id: u8,
+        map: &RegisterMap,
+        name: &str
+    ) -> Result<f64> {
+        let point = map
+            .get(name)
+            .ok_or_else(|| Error::UnknownPoint(name.to_string()))?;
+        if !point.access.is_readable() {
+            return Err(Error::InvalidPoint {
+                name:   name.to_string(),
+                reason: "write only".to_string()
+            });
+        }
+        let rs = self
+            .call(point.read_request(unit_id))
+            .await?
+            .into_result()?;
+        point.decode(&rs).ok_or(Error::UnexpectedResponse)
+    }
+
+    /// Modify bits of a holding register (Function Code: 0x16)
+    ///
+    /// Bits set in `and_mask` are kept, the others are taken from
+    /// `or_mask`, see `apply_mask`. Unlike a read followed by a
+    /// write, the server applies the mask atomically. A response
+    /// which doesn't echo the request fails with
+    /// `Error::UnexpectedResponse`.
+    pub async fn mask_write_register(
+        &mut self,
+        unit_id: u8,
+        address: u16,
+        and_mask: u16,
+        or_mask: u16
+    ) -> Result<()> {
+        let request = Request::mask_write_register_request(
+            unit_id, address, and_mask, or_mask
+        );
+        let Some(rs) = self.call_write(request).await? else {
+            return Ok(());
+        };
+        match rs {
+            Response::MaskWriteRegister(_, rq, rs) => {
+                // the response echoes the request
+                let rs = rs?;
+                if (rs.address, rs.and_mask, rs.or_mask)
+                    != (rq.address, rq.and_mask, rq.or_mask)
+                {
+                    return Err(Error::UnexpectedResponse);
+                }
+            },
+            _ => return Err(Error::UnexpectedResponse)
+        }
+        Ok(())
+    }
+
+    /// Write holding registers, then read holding registers in one
+    /// transaction (Function Code: 0x17)
+    ///
+    /// At most 121 registers can be written and 125 read, other
+    /// quantities fail with `Error::InvalidQuantity`.
+    pub async fn read_write_multiple_registers(
+        &mut self,
+        unit_id: u8,
+        read_address: u16,
+        read_quantity: u16,
+        write_address: u16,
+        values: &[u16]
+    ) -> Result<Vec<u16>> {
+        let request = Request::read_write_multiple_registers_request(
+            unit_id,
+            read_address,
+            read_quantity,
+            write_address,
+            registers_to_bytes(values)
+        );
+        match self.call(request).await? {
+            Response::ReadWriteMultipleRegisters(_, _, rs) => {
+                Ok(bytes_to_registers(&rs?.get_values()))
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read the eight exception status outputs (Function Code: 0x07)
+    pub async fn read_exception_status(
+        &mut self,
+        unit_id: u8
+    ) -> Result<u8> {
+        let request = Request::read_exception_status_request(unit_id);
+        match self.call(request).await? {
+            Response::ReadExceptionStatus(_, _, rs) => Ok(rs?.status),
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Run a diagnostic (Function Code: 0x08) and return the data
+    /// field of the response
+    ///
+    /// `DiagnosticsSubFunction::ForceListenOnlyMode` isn't answered,
+    /// use `force_listen_only_mode`.
+    pub async fn diagnostics(
+        &mut self,
+        unit_id: u8,
+        sub_function: DiagnosticsSubFunction,
+        data: &[u8]
+    ) -> Result<Vec<u8>> {
+        let request = Request::diagnostics_request(
+            unit_id,
+            sub_function,
+            data.to_vec()
+        );
+        match self.call(request).await? {
+            Response::Diagnostics(_, _, rs) => {
+                let rs = rs?;
+                if rs.sub_function != sub_function {
+                    return Err(Error::UnexpectedResponse);
+                }
+                Ok(rs.data)
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Check the communication with a device, which has to echo
+    /// `data` (Function Code: 0x08, Sub-function: 0x00)
+    pub async fn return_query_data(
+        &mut self,
+        unit_id: u8,
+        data: &[u8]
+    ) -> Result<()> {
+        let echo = self
+            .diagnostics(
+                unit_id,
+                DiagnosticsSubFunction::ReturnQueryData,
+                data
+            )
+            .await?;
+        if echo != data {
+            return Err(Error::UnexpectedResponse);
+        }
+        Ok(())
+    }
+
+    /// Restart the serial line port of a device and leave listen only
+    /// mode, `clear_log` also clears its communications event log
+    /// (Function Code: 0x08, Sub-function: 0x01)
+    pub async fn restart_communications(
+        &mut self,
+        unit_id: u8,
+        clear_log: bool
+    ) -> Result<()> {
+        let data: u16 = if clear_log { 0xFF00 } else { 0x0000 };
+        self.diagnostics(
+            unit_id,
+            DiagnosticsSubFunction::RestartCommunicationsOption,
+            &data.to_be_bytes()
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Read the diagnostic register or a counter of a device, e.g.
+    /// `DiagnosticsSubFunction::ReturnBusCommunicationErrorCount`
+    /// (Function Code: 0x08)
+    pub async fn diagnostic_counter(
+        &mut self,
+        unit_id: u8,
+        sub_function: DiagnosticsSubFunction
+    ) -> Result<u16> {
+        let data = self
+            .diagnostics(unit_id, sub_function, &[0x00, 0x00])
+            .await?;
+        match data[..] {
+            [high, low] => Ok(u16::from_be_bytes([high, low])),
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Clear the counters and the diagnostic register of a device
+    /// (Function Code: 0x08, Sub-function: 0x0A)
+    pub async fn clear_diagnostic_counters(
+        &mut self,
+        unit_id: u8
+    ) -> Result<()> {
+        self.diagnostics(
+            unit_id,
+            DiagnosticsSubFunction::ClearCountersAndDiagnosticRegister,
+            &[0x00, 0x00]
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Force a device into listen only mode (Function Code: 0x08,
+    /// Sub-function: 0x04)
+    ///
+    /// The device doesn't answer this nor any further request until
+    /// `restart_communications`.
+    pub async fn force_listen_only_mode(
+        &mut self,
+        unit_id: u8
+    ) -> Result<()> {
+        let request = Request::diagnostics_request(
+            unit_id,
+            DiagnosticsSubFunction::ForceListenOnlyMode,
+            vec![0x00, 0x00]
+        );
+        self.send(request).await
+    }
+
+    /// Read the status word and the event counter of a device
+    /// (Function Code: 0x0B)
+    pub async fn get_comm_event_counter(
+        &mut self,
+        unit_id: u8
+    ) -> Result<GetCommEventCounterResponse> {
+        let request =
+            Request::get_comm_event_counter_request(unit_id);
+        match self.call(request).await? {
+            Response::GetCommEventCounter(_, _, rs) => Ok(rs?),
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read the status word, the counters and the event log of a
+    /// device (Function Code: 0x0C)
+    pub async fn get_comm_event_log(
+        &mut self,
+        unit_id: u8
+    ) -> Result<GetCommEventLogResponse> {
+        let request = Request::get_comm_event_log_request(unit_id);
+        match self.call(request).await? {
+            Response::GetCommEventLog(_, _, rs) => Ok(rs?),
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read the server id, the run indicator status and the
+    /// additional data of a device, whose layout is device specific
+    /// (Function Code: 0x11)
+    pub async fn report_server_id(
+        &mut self,
+        unit_id: u8
+    ) -> Result<Vec<u8>> {
+        let request = Request::report_server_id_request(unit_id);
+        match self.call(request).await? {
+            Response::ReportServerId(_, _, rs) => {
+                Ok(rs?.get_values())
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Read file records, the registers of each sub-request in order
+    /// (Function Code: 0x14)
+    ///
+    /// Request and response must not exceed `FILE_RECORD_MAX_BYTES`,
+    /// see `read_file` to read more records.
+    pub async fn read_file_record(
+        &mut self,
+        unit_id: u8,
+        sub_requests: Vec<FileSubRequest>
+    ) -> Result<Vec<Vec<u16>>> {
+        let lengths: Vec<usize> = sub_requests
+            .iter()
+            .map(|sub| sub.record_length as usize)
+            .collect();
+        let request =
+            Request::read_file_record_request(unit_id, sub_requests);
+        let records = match self.call(request).await? {
+            Response::ReadFileRecord(_, _, rs) => rs?.records,
+            _ => return Err(Error::UnexpectedResponse)
+        };
+        let complete = records.len() == lengths.len()
+            && records
+                .iter()
+                .zip(&lengths)
+                .all(|(r, len)| r.len() == *len);
+        if !complete {
+            return Err(Error::UnexpectedResponse);
+        }
+        Ok(records)
+    }
+
+    /// Write file records (Function Code: 0x15)
+    ///
+    /// The request must not exceed `FILE_RECORD_MAX_BYTES`, see
+    /// `write_file` to write more records.
+    pub async fn write_file_record(
+        &mut self,
+        unit_id: u8,
+        records: Vec<FileRecord>
+    ) -> Result<()> {
+        let request =
+            Request::write_file_record_request(unit_id, records);
+        let Some(rs) = self.call_write(request).await? else {
+            return Ok(());
+        };
+        match rs {
+            Response::WriteFileRecord(_, _, rs) => rs.map(|_| ())?,
+            _ => return Err(Error::UnexpectedResponse)
+        }
+        Ok(())
+    }
+
+    /// Read `record_count` records of file `file_number`, starting
+    /// with record 0
+    ///
+    /// The records are read with as many read file record requests
+    /// as needed, each PDU stays within 253 bytes. A file has at most
+    /// `FILE_MAX_RECORD_NUMBER + 1` records, more fail with
+    /// `Error::InvalidQuantity`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::TcpClient;
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let profile = client.read_file(0x01, 0x0004, 1000).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_file(
+        &mut self,
+        unit_id: u8,
+        file_number: u16,
+        record_count: u16
+    ) -> Result<Vec<u16>> {
+        if !records_in_file(0, record_count as usize) {
+            return Err(Error::InvalidQuantity(record_count));
+        }
+        let mut values = Vec::with_capacity(record_count as usize);
+        let mut record_number = 0;
+        while record_number < record_count {
+            let record_length = (record_count - record_number)
+                .min(ReadFileRecordRequest::MAX_RECORD_LENGTH);
+            let sub_request = FileSubRequest::new(
+                file_number,
+                record_number,
+                record_length
+            );
+            let mut records = self
+                .read_file_record(unit_id, vec![sub_request])
+                .await?;
+            values.append(&mut records[0]);
+            record_number += record_length;
+        }
+        Ok(values)
+    }
+
+    /// Write `values` to file `file_number`, starting with record
+    /// `record_number`
+    ///
+    /// The values are written with as many write file record
+    /// requests as needed, each PDU stays within 253 bytes. Values
+    /// past `FILE_MAX_RECORD_NUMBER` fail with
+    /// `Error::InvalidQuantity` before anything is written.
+    pub async fn write_file(
+        &mut self,
+        unit_id: u8,
+        file_number: u16,
+        record_number: u16,
+        values: &[u16]
+    ) -> Result<()> {
+        if !records_in_file(record_number, values.len()) {
+            let quantity =
+                u16::try_from(values.len()).unwrap_or(u16::MAX);
+            return Err(Error::InvalidQuantity(quantity));
+        }
+        let chunks = values.chunks(
+            WriteFileRecordRequest::MAX_RECORD_LENGTH as usize
+        );
+        let mut record_number = record_number;
+        for chunk in chunks {
+            let record = FileRecord::new(
+                file_number,
+                record_number,
+                chunk.to_vec()
+            );
+            self.write_file_record(unit_id, vec![record]).await?;
+            record_number += chunk.len() as u16;
+        }
+        Ok(())
+    }
+
+    /// Read the registers of a FIFO queue, the first queued
+    /// register first (Function Code: 0x18)
+    ///
+    /// A queue of more than 31 registers is answered with an illegal
+    /// data value exception, i.e. `Error::Exception`.
+    pub async fn read_fifo_queue(
+        &mut self,
+        unit_id: u8,
+        fifo_address: u16
+    ) -> Result<Vec<u16>> {
+        let request =
+            Request::read_fifo_queue_request(unit_id, fifo_address);
+        let rs = match self.call(request).await? {
+            Response::ReadFifoQueue(_, _, rs) => rs?,
+            _ => return Err(Error::UnexpectedResponse)
+        };
+        if rs.fifo_count > ReadFifoQueueRequest::MAX_FIFO_COUNT {
+            return Err(Error::InvalidQuantity(rs.fifo_count));
+        }
+        let values = bytes_to_registers(&rs.get_values());
+        if values.len() != rs.fifo_count as usize {
+            return Err(Error::UnexpectedResponse);
+        }
+        Ok(values)
+    }
+
+    /// Read the identification of a device (Function Code: 0x2B, MEI
+    /// type: 0x0E)
+    ///
+    /// Stream access types request the following pages as long as
+    /// the device reports more objects, starting with `object_id`
+    /// (usually 0x00). Individual access reads only the object
+    /// `object_id`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{ReadDeviceIdCode, TcpClient};
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let identification = client
+    ///     .read_device_identification(0x01, ReadDeviceIdCode::Regular, 0x00)
+    ///     .await?;
+    /// println!("{:?}", identification.vendor_name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_device_identification(
+        &mut self,
+        unit_id: u8,
+        read_device_id_code: ReadDeviceIdCode,
+        object_id: u8
+    ) -> Result<DeviceIdentification> {
+        let mut identification = DeviceIdentification::default();
+        let mut object_id = object_id;
+        loop {
+            let request = Request::read_device_identification_request(
+                unit_id,
+                read_device_id_code,
+                object_id
+            );
+            let rs = match self.call(request).await? {
+                Response::ReadDeviceIdentification(_, _, rs) => rs?,
+                _ => return Err(Error::UnexpectedResponse)
+            };
+            identification.conformity_level = rs.conformity_level;
+            for (id, value) in rs.objects {
+                identification.insert(id, value);
+            }
+            if !rs.more_follows
+                || read_device_id_code == ReadDeviceIdCode::Individual
+            {
+                return Ok(identification);
+            }
+            // a device repeating a page would be asked forever
+            if rs.next_object_id <= object_id {
+                return Err(Error::UnexpectedResponse);
+            }
+            object_id = rs.next_object_id;
+        }
+    }
+
+    /// Call a user defined function code and return the data of the
+    /// response following the function code
+    ///
+    /// `response_len` computes the length of the response, see
+    /// `CustomFunction`. Like writes, custom requests are only
+    /// retried if the retry policy allows retrying writes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::TcpClient;
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let data = client
+    ///     .call_custom(0x01, 0x41, &[0x00, 0x10], |_: &[u8], data: &[u8]| {
+    ///         data.first().map(|&bytes_num| bytes_num as usize + 1)
+    ///     })
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_custom(
+        &mut self,
+        unit_id: u8,
+        function: u8,
+        payload: &[u8],
+        response_len: impl CustomFunction + 'static
+    ) -> Result<Vec<u8>> {
+        let request = Request::custom_request(
+            unit_id,
+            function,
+            payload.to_vec(),
+            response_len
+        )?;
+        match self.call(request).await? {
+            Response::Custom(_, _, rs) => Ok(rs?.payload),
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+
+    /// Send a CANopen general reference request and return the
+    /// payload of the response following the MEI type (Function
+    /// Code: 0x2B, MEI type: 0x0D)
+    ///
+    /// `response_len` computes the length of the response payload,
+    /// see `CustomFunction`.
+    pub async fn canopen_general_reference(
+        &mut self,
+        unit_id: u8,
+        payload: &[u8],
+        response_len: impl CustomFunction + 'static
+    ) -> Result<Vec<u8>> {
+        let request = Request::canopen_general_reference_request(
+            unit_id,
+            payload.to_vec(),
+            response_len
+        );
+        self.call_canopen(request).await
+    }
+
+    /// Read a CANopen object by an expedited SDO upload, e.g. 1 to 4
+    /// bytes, least significant byte first
+    ///
+    /// An abort of the transfer by the node is reported as
+    /// `Error::SdoAbort`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::TcpClient;
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// // vendor id of the drive behind unit 2
+    /// let vendor_id = client.sdo_upload(0x02, 0x1018, 0x01).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sdo_upload(
+        &mut self,
+        unit_id: u8,
+        index: u16,
+        subindex: u8
+    ) -> Result<Vec<u8>> {
+        let request =
+            Request::sdo_upload_request(unit_id, index, subindex);
+        let payload = self.call_canopen(request).await?;
+        SdoResponse::parse(&payload, index, subindex)
+            .ok_or(Error::UnexpectedResponse)?
+            .into_upload()
+    }
+
+    /// Write 1 to 4 bytes to a CANopen object by an expedited SDO
+    /// download, least significant byte first
+    ///
+    /// An abort of the transfer by the node is reported as
+    /// `Error::SdoAbort`.
+    pub async fn sdo_download(
+        &mut self,
+        unit_id: u8,
+        index: u16,
+        subindex: u8,
+        data: &[u8]
+    ) -> Result<()> {
+        let request = Request::sdo_download_request(
+            unit_id, index, subindex, data
+        )
+        .ok_or_else(|| {
+            Error::InvalidQuantity(
+                u16::try_from(data.len()).unwrap_or(u16::MAX)
+            )
+        })?;
+        let payload = self.call_canopen(request).await?;
+        SdoResponse::parse(&payload, index, subindex)
+            .ok_or(Error::UnexpectedResponse)?
+            .into_download()
+    }
+
+    async fn call_canopen(
+        &mut self,
+        request: Request
+    ) -> Result<Vec<u8>> {
+        match self.call(request).await? {
+            Response::CanopenGeneralReference(_, _, rs) => {
+                let rs = rs?;
+                if rs.mei_type != MEI_CANOPEN_GENERAL_REFERENCE {
+                    return Err(Error::UnexpectedResponse);
+                }
+                Ok(rs.payload)
+            },
+            _ => Err(Error::UnexpectedResponse)
+        }
+    }
+}
+
+/// Number of registers of `count` values of type `V`
+fn values_quantity<V: RegisterValue>(count: u16) -> Result<u16> {
+    count
+        .checked_mul(V::REGISTERS as u16)
+        .ok_or(Error::InvalidQuantity(count))
+}
+
+/// Decode exactly `count` values of type `V`
+fn decode_values<V: RegisterValue>(
+    registers: &[u16],
+    count: u16,
+    order: WordOrder
+) -> Result<Vec<V>> {
+    data::decode(registers, order)
+        .filter(|values: &Vec<V>| values.len() == count as usize)
+        .ok_or(Error::UnexpectedResponse)
+}
+
+/// Split `quantity` items from `address` into requests of at most
+/// `max` items, a quantity of 0 is sent as is
+fn split(
+    address: u16,
+    quantity: u16,
+    max: u16
+) -> Result<Vec<(u16, u16)>> {
+    if address as u32 + quantity as u32 > u16::MAX as u32 + 1 {
+        return Err(Error::InvalidQuantity(quantity));
+    }
+    if quantity == 0 {
+        return Ok(vec![(address, 0)]);
+    }
+    let chunks = (0..quantity)
+        .step_by(max as usize)
+        .map(|offset| {
+            (address + offset, (quantity - offset).min(max))
+        })
+        .collect();
+    Ok(chunks)
+}
+
+/// Split `values` written from `address` into requests of at most
+/// `max` values
+fn split_values<V>(
+    address: u16,
+    values: &[V],
+    max: u16
+) -> Result<Vec<(u16, &[V])>> {
+    let quantity = u16::try_from(values.len())
+        .map_err(|_| Error::InvalidQuantity(u16::MAX))?;
+    let chunks = split(address, quantity, max)?
+        .into_iter()
+        .map(|(chunk_address, quantity)| {
+            let start = (chunk_address - address) as usize;
+            (chunk_address, &values[start..start + quantity as usize])
+        })
+        .collect();
+    Ok(chunks)
+}
+
+/// Exception responses to one of several requests carry the address
+/// of the failed request
+fn chunk_result<V>(
+    rs: Result<V>,
+    address: u16,
+    chunks: usize
+) -> Result<V> {
+    match rs {
+        Err(Error::Exception(exception)) if chunks > 1 => {
+            Err(Error::ChunkException { address, exception })
+        },
+        rs => rs
+    }
+}
+
+/// `Error::UnexpectedResponse` unless `quantity` items were read
+fn check_len<V>(values: Vec<V>, quantity: u16) -> Result<Vec<V>> {
+    if values.len() != quantity as usize {
+        return Err(Error::UnexpectedResponse);
+    }
+    Ok(values)
+}
+
+/// Whether the connection is unusable after `e`, a TLS session
+/// can't continue after an alert or a corrupted record
+fn is_fatal(e: &Error) -> bool {
+    match e {
+        Error::Io(_) => true,
+        #[cfg(feature = "tls")]
+        Error::Tls(_) => true,
+        _ => false
+    }
+}
+
+/// Await `future` for at most `timeout`
+async fn with_timeout<F: std::future::Future>(
+    timeout: Option<Duration>,
+    future: F
+) -> Result<F::Output> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .map_err(|_| Error::Timeout),
+        None => Ok(future.await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use easy_modbus::util::crc;
+    use tokio::{
+        io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream},
+        task::JoinHandle
+    };
+
+    use super::*;
+    use crate::{DataType, Exception, Point, Table};
+
+    fn with_crc(frame: Vec<u8>) -> Vec<u8> {
+        let crc = crc::compute(&frame);
+        [frame, crc.to_be_bytes().to_vec()].concat()
+    }
+
+    /// RTU server which expects each request of `exchanges` and
+    /// answers it with the response, both without CRC, an empty
+    /// response isn't sent. Returns its end of the stream, which
+    /// stays open until then.
+    fn serve(
+        mut server: DuplexStream,
+        exchanges: Vec<(Vec<u8>, Vec<u8>)>
+    ) -> JoinHandle<DuplexStream> {
+        tokio::spawn(async move {
+            for (request, response) in exchanges {
+                let request = with_crc(request);
+                let mut buf = vec![0u8; request.len()];
+                server.read_exact(&mut buf).await.unwrap();
+                assert_eq!(buf, request);
+                if !response.is_empty() {
+                    server
+                        .write_all(&with_crc(response))
+                        .await
+                        .unwrap();
+                }
+            }
+            server
+        })
+    }
+
+    /// RTU client of a `serve`d server
+    fn rtu(
+        exchanges: Vec<(Vec<u8>, Vec<u8>)>
+    ) -> (Client<DuplexStream, RtuCodec>, JoinHandle<DuplexStream>)
+    {
+        let (transport, server) = duplex(1024);
+        let server = serve(server, exchanges);
+        (Client::new(transport, RtuCodec::default()), server)
+    }
+
+    #[tokio::test]
+    async fn borrowed_transport() {
+        let (mut transport, server) = duplex(1024);
+        let server = serve(
+            server,
+            vec![(
+                vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01],
+                vec![0x01, 0x03, 0x02, 0x00, 0x05]
+            )]
+        );
+        let mut client =
+            Client::new(&mut transport, RtuCodec::default());
+        let registers =
+            client.read_holding_registers(0x01, 0x0000, 1).await;
+        assert_eq!(registers.unwrap(), [0x0005]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_past_last_record() {
+        let (mut client, _) = rtu(vec![]);
+        let rs = client.read_file(0x01, 0x0004, 0x2711).await;
+        assert!(matches!(rs, Err(Error::InvalidQuantity(0x2711))));
+        let rs =
+            client.write_file(0x01, 0x0004, 0x270F, &[1, 2]).await;
+        assert!(matches!(rs, Err(Error::InvalidQuantity(2))));
+    }
+
+    #[tokio::test]
+    async fn sdo_upload_retried() {
+        let request = vec![
+            0x02, 0x2B, 0x0D, 0x40, 0x18, 0x10, 0x01, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        let busy = vec![0x02, 0xAB, 0x06];
+        let upload = vec![
+            0x02, 0x2B, 0x0D, 0x43, 0x18, 0x10, 0x01, 0x78, 0x56,
+            0x34, 0x12,
+        ];
+        let (mut client, server) =
+            rtu(vec![(request.clone(), busy), (request, upload)]);
+        client.set_retry_policy(RetryPolicy {
+            backoff: Duration::ZERO,
+            ..RetryPolicy::default()
+        });
+        let data =
+            client.sdo_upload(0x02, 0x1018, 0x01).await.unwrap();
+        assert_eq!(data, [0x78, 0x56, 0x34, 0x12]);
+        server.await.unwrap();
+
+        let download = Request::sdo_download_request(
+            0x02,
+            0x6040,
+            0x00,
+            &[0x0F]
+        );
+        assert!(download.unwrap().is_write());
+    }
+
+    #[tokio::test]
+    async fn canopen_other_mei_type() {
+        let (mut client, server) = rtu(vec![(
+            vec![
+                0x02, 0x2B, 0x0D, 0x40, 0x18, 0x10, 0x01, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+            vec![
+                0x02, 0x2B, 0x0E, 0x43, 0x18, 0x10, 0x01, 0x78, 0x56,
+                0x34, 0x12,
+            ]
+        )]);
+        let rs = client.sdo_upload(0x02, 0x1018, 0x01).await;
+        assert!(matches!(rs, Err(Error::UnexpectedResponse)));
+        server.await.unwrap();
+    }
+
+    /// Response to a read holding registers request of `quantity`
+    /// registers, all 0
+    fn registers(quantity: u16) -> Vec<u8> {
+        let mut rs = vec![0x01, 0x03, (quantity * 2) as u8];
+        rs.resize(3 + quantity as usize * 2, 0);
+        rs
+    }
+
+    #[test]
+    fn split_registers() {
+        let max = Request::MAX_READ_REGISTERS;
+        assert_eq!(split(0x0010, 125, max).unwrap(), [(0x0010, 125)]);
+        assert_eq!(
+            split(0x0010, 126, max).unwrap(),
+            [(0x0010, 125), (0x008D, 1)]
+        );
+        assert_eq!(
+            split(0x0010, 246, Request::MAX_WRITE_REGISTERS).unwrap(),
+            [(0x0010, 123), (0x008B, 123)]
+        );
+    }
+
+    #[test]
+    fn split_bits() {
+        let max = Request::MAX_READ_BITS;
+        assert_eq!(
+            split(0x0000, 2000, max).unwrap(),
+            [(0x0000, 2000)]
+        );
+        assert_eq!(
+            split(0x0000, 2001, max).unwrap(),
+            [(0x0000, 2000), (0x07D0, 1)]
+        );
+        assert_eq!(
+            split(0x0000, 1969, Request::MAX_WRITE_BITS).unwrap(),
+            [(0x0000, 1968), (0x07B0, 1)]
+        );
+    }
+
+    #[test]
+    fn split_past_last_address() {
+        let max = Request::MAX_READ_REGISTERS;
+        assert_eq!(split(0xFFFF, 1, max).unwrap(), [(0xFFFF, 1)]);
+        assert_eq!(
+            split(0xFF00, 0x0100, max).unwrap().last(),
+            Some(&(0xFFFA, 6))
+        );
+        assert!(matches!(
+            split(0xFFFF, 2, max),
+            Err(Error::InvalidQuantity(2))
+        ));
+        assert!(matches!(
+            split(0x0002, u16::MAX, max),
+            Err(Error::InvalidQuantity(u16::MAX))
+        ));
+        assert_eq!(split(0x0100, 0, max).unwrap(), [(0x0100, 0)]);
+    }
+
+    #[test]
+    fn split_write_values() {
+        let values: Vec<u16> = (0..247).collect();
+        let chunks = split_values(
+            0x0010,
+            &values,
+            Request::MAX_WRITE_REGISTERS
+        )
+        .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0], (0x0010, &values[..123]));
+        assert_eq!(chunks[1], (0x008B, &values[123..246]));
+        assert_eq!(chunks[2], (0x0106, &values[246..]));
+        assert!(matches!(
+            split_values(
+                0xFFFF,
+                &values,
+                Request::MAX_WRITE_REGISTERS
+            ),
+            Err(Error::InvalidQuantity(247))
+        ));
+        let values = vec![0u16; u16::MAX as usize + 1];
+        assert!(matches!(
+            split_values(
+                0x0000,
+                &values,
+                Request::MAX_WRITE_REGISTERS
+            ),
+            Err(Error::InvalidQuantity(u16::MAX))
+        ));
+    }
+
+    #[tokio::test]
+    async fn chunk_exception_address() {
+        let (mut client, server) = rtu(vec![
+            (
+                vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x7D],
+                registers(125)
+            ),
+            (
+                vec![0x01, 0x03, 0x00, 0x7D, 0x00, 0x7D],
+                vec![0x01, 0x83, 0x02]
+            ),
+        ]);
+        let rs =
+            client.read_holding_registers(0x01, 0x0000, 300).await;
+        assert!(matches!(
+            rs,
+            Err(Error::ChunkException {
+                address:   0x007D,
+                exception: Exception::IllegalDataAddress
+            })
+        ));
+        server.await.unwrap();
+    }
+
+    /// Block longer than a single read request
+    struct Block(Vec<u16>);
+
+    impl ModbusRegisters for Block {
+        const REGISTERS: u16 = 130;
+
+        fn from_registers(registers: &[u16]) -> Option<Self> {
+            Some(Block(registers.get(..130)?.to_vec()))
+        }
+
+        fn to_registers(&self) -> Vec<u16> {
+            self.0.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn read_long_block() {
+        let (mut client, server) = rtu(vec![
+            (
+                vec![0x01, 0x03, 0x01, 0x00, 0x00, 0x7D],
+                registers(125)
+            ),
+            (vec![0x01, 0x03, 0x01, 0x7D, 0x00, 0x05], registers(5)),
+        ]);
+        let block: Block =
+            client.read_block(0x01, 0x0100).await.unwrap();
+        assert_eq!(block.0, [0; 130]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn broadcast_unanswered() {
+        let (mut client, server) = rtu(vec![
+            (vec![0x00, 0x06, 0x00, 0x10, 0x00, 0x01], vec![]),
+            (
+                vec![0x01, 0x03, 0x00, 0x10, 0x00, 0x01],
+                vec![0x01, 0x03, 0x02, 0x00, 0x05]
+            ),
+        ]);
+        let start = Instant::now();
+        let request = Request::write_single_holding_register_request(
+            0x01, 0x0010, 0x0001
+        );
+        client.broadcast(request).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        // the next request waits out the turnaround delay
+        let registers =
+            client.read_holding_registers(0x01, 0x0010, 1).await;
+        assert_eq!(registers.unwrap(), [0x0005]);
+        assert!(start.elapsed() >= client.turnaround_delay());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn broadcast_read() {
+        let (mut client, _) = rtu(vec![]);
+        let request =
+            Request::read_multiple_holding_registers_request(
+                0x00, 0x0010, 1
+            );
+        let rs = client.broadcast(request.clone()).await;
+        assert!(matches!(rs, Err(Error::InvalidBroadcast)));
+        let rs = client.call(request).await;
+        assert!(matches!(rs, Err(Error::InvalidBroadcast)));
+        let request = Request::read_write_multiple_registers_request(
+            0x00,
+            0x0010,
+            1,
+            0x0020,
+            vec![0x00, 0x01]
+        );
+        let rs = client.broadcast(request).await;
+        assert!(matches!(rs, Err(Error::InvalidBroadcast)));
+    }
+
+    #[tokio::test]
+    async fn broadcast_over_tcp() {
+        let (transport, _server) = duplex(1024);
+        let mut client = Client::new(transport, TcpCodec::default());
+        let request = Request::write_single_holding_register_request(
+            0x00, 0x0010, 0x0001
+        );
+        let rs = client.broadcast(request).await;
+        assert!(matches!(rs, Err(Error::InvalidBroadcast)));
+    }
+
+    #[tokio::test]
+    async fn fifo_queue_too_long() {
+        let mut response = vec![0x01, 0x18, 0x00, 0x42, 0x00, 0x20];
+        response.resize(response.len() + 64, 0x00);
+        let (mut client, server) = rtu(vec![
+            (vec![0x01, 0x18, 0x04, 0xDE], response),
+            (
+                vec![0x01, 0x18, 0x04, 0xDE],
+                vec![0x01, 0x18, 0x00, 0x04, 0x00, 0x01, 0x12, 0x84]
+            ),
+        ]);
+        let rs = client.read_fifo_queue(0x01, 0x04DE).await;
+        assert!(matches!(rs, Err(Error::InvalidQuantity(32))));
+        let values = client.read_fifo_queue(0x01, 0x04DE).await;
+        assert_eq!(values.unwrap(), [0x1284]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn silent_server_timeout() {
+        let (transport, _server) = duplex(1024);
+        let mut client = Client::new(transport, RtuCodec::default());
+        client.set_retry_policy(RetryPolicy::none());
+        let start = Instant::now();
+        let rs = client.read_holding_registers(0x01, 0x0000, 1).await;
+        assert!(matches!(rs, Err(Error::Timeout)));
+        assert_eq!(
+            start.elapsed(),
+            Client::<DuplexStream, RtuCodec>::DEFAULT_TIMEOUT
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_per_call() {
+        let (transport, _server) = duplex(1024);
+        let mut client = Client::new(transport, RtuCodec::default());
+        client.set_retry_policy(RetryPolicy::none());
+        let request =
+            Request::read_multiple_holding_registers_request(
+                0x01, 0x0000, 1
+            );
+        let start = Instant::now();
+        let timeout = Duration::from_millis(50);
+        let rs =
+            client.call_with_timeout(request, Some(timeout)).await;
+        assert!(matches!(rs, Err(Error::Timeout)));
+        assert_eq!(start.elapsed(), timeout);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn partial_frame_discarded() {
+        let (transport, mut server) = duplex(1024);
+        let mut client = Client::new(transport, RtuCodec::default());
+        client.set_retry_policy(RetryPolicy::none());
+        let request =
+            with_crc(vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01]);
+        let server = tokio::spawn(async move {
+            let mut buf = vec![0u8; request.len()];
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, request);
+            // start of a response which never ends
+            server.write_all(&[0x01, 0x03, 0x02]).await.unwrap();
+            server.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, request);
+            let response =
+                with_crc(vec![0x01, 0x03, 0x02, 0x00, 0x05]);
+            server.write_all(&response).await.unwrap();
+            server
+        });
+        let rs = client.read_holding_registers(0x01, 0x0000, 1).await;
+        assert!(matches!(rs, Err(Error::Timeout)));
+        let registers =
+            client.read_holding_registers(0x01, 0x0000, 1).await;
+        assert_eq!(registers.unwrap(), [0x0005]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn read_retried_on_timeout() {
+        let request = vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01];
+        let (mut client, server) = rtu(vec![
+            (request.clone(), vec![]),
+            (request, vec![0x01, 0x03, 0x02, 0x00, 0x05]),
+        ]);
+        let registers =
+            client.read_holding_registers(0x01, 0x0000, 1).await;
+        assert_eq!(registers.unwrap(), [0x0005]);
+        server.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn write_not_retried() {
+        let request = vec![0x01, 0x06, 0x00, 0x10, 0x00, 0x01];
+        let (mut client, server) = rtu(vec![(request, vec![])]);
+        let rs =
+            client.write_single_register(0x01, 0x0010, 0x0001).await;
+        assert!(matches!(rs, Err(Error::Timeout)));
+        // nothing was sent after the first request
+        let mut server = server.await.unwrap();
+        drop(client);
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn write_retried_if_enabled() {
+        let request = vec![0x01, 0x06, 0x00, 0x10, 0x00, 0x01];
+        let (mut client, server) = rtu(vec![
+            (request.clone(), vec![]),
+            (request.clone(), request),
+        ]);
+        client.set_retry_policy(RetryPolicy {
+            retry_writes: true,
+            ..RetryPolicy::default()
+        });
+        client
+            .write_single_register(0x01, 0x0010, 0x0001)
+            .await
+            .unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exception_not_retried() {
+        let (mut client, server) = rtu(vec![(
+            vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x01],
+            vec![0x01, 0x83, 0x02]
+        )]);
+        let rs = client.read_holding_registers(0x01, 0x0000, 1).await;
+        assert!(matches!(
+            rs,
+            Err(Error::Exception(Exception::IllegalDataAddress))
+        ));
+        let mut server = server.await.unwrap();
+        drop(client);
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+    }
+
+    #[tokio::test]
+    async fn device_identification_pages() {
+        let (mut client, server) = rtu(vec![
+            (
+                vec![0x01, 0x2B, 0x0E, 0x01, 0x00],
+                vec![
+                    0x01, 0x2B, 0x0E, 0x01, 0x81, 0xFF, 0x01, 0x01,
+                    0x00, 0x03, b'A', b'C', b'M',
+                ]
+            ),
+            (
+                vec![0x01, 0x2B, 0x0E, 0x01, 0x01],
+                vec![
+                    0x01, 0x2B, 0x0E, 0x01, 0x81, 0x00, 0x00, 0x02,
+                    0x01, 0x02, b'P', b'1', 0x02, 0x03, b'1', b'.',
+                    b'0',
+                ]
+            ),
+        ]);
+        let identification = client
+            .read_device_identification(
+                0x01,
+                ReadDeviceIdCode::Basic,
+                0
+            )
+            .await
+            .unwrap();
+        assert_eq!(identification.conformity_level, 0x81);
+        assert_eq!(
+            identification.vendor_name.as_deref(),
+            Some("ACM")
+        );
+        assert_eq!(
+            identification.product_code.as_deref(),
+            Some("P1")
+        );
+        assert_eq!(
+            identification.major_minor_revision.as_deref(),
+            Some("1.0")
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn device_identification_repeated_page() {
+        let (mut client, server) = rtu(vec![
+            (
+                vec![0x01, 0x2B, 0x0E, 0x01, 0x00],
+                vec![
+                    0x01, 0x2B, 0x0E, 0x01, 0x81, 0xFF, 0x01, 0x01,
+                    0x00, 0x03, b'A', b'C', b'M',
+                ]
+            ),
+            // the next object id doesn't advance
+            (
+                vec![0x01, 0x2B, 0x0E, 0x01, 0x01],
+                vec![
+                    0x01, 0x2B, 0x0E, 0x01, 0x81, 0xFF, 0x01, 0x01,
+                    0x01, 0x02, b'P', b'1',
+                ]
+            ),
+        ]);
+        let rs = client
+            .read_device_identification(
+                0x01,
+                ReadDeviceIdCode::Basic,
+                0
+            )
+            .await;
+        assert!(matches!(rs, Err(Error::UnexpectedResponse)));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn mask_write_register_echo() {
+        let request =
+            vec![0x01, 0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25];
+        let (mut client, server) = rtu(vec![
+            (request.clone(), request.clone()),
+            (
+                request,
+                vec![0x01, 0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x24]
+            ),
+        ]);
+        client
+            .mask_write_register(0x01, 0x0004, 0x00F2, 0x0025)
+            .await
+            .unwrap();
+        let rs = client
+            .mask_write_register(0x01, 0x0004, 0x00F2, 0x0025)
+            .await;
+        assert!(matches!(rs, Err(Error::UnexpectedResponse)));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn read_scaled_point() {
+        let mut power = Point::new(
+            "power",
+            Table::Holding,
+            0x0010,
+            DataType::I32
+        );
+        power.scale = 0.5;
+        power.offset = 100.0;
+        let map = RegisterMap::new(vec![power]).unwrap();
+        let (mut client, server) = rtu(vec![(
+            vec![0x01, 0x03, 0x00, 0x10, 0x00, 0x02],
+            // -300
+            vec![0x01, 0x03, 0x04, 0xFF, 0xFF, 0xFE, 0xD4]
+        )]);
+        let value = client.read_point(0x01, &map, "power").await;
+        assert_eq!(value.unwrap(), -50.0);
+        let rs = client.read_point(0x01, &map, "voltage").await;
+        assert!(matches!(rs, Err(Error::UnknownPoint(_))));
+        server.await.unwrap();
+    }
+}
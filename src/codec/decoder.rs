@@ -1,15 +1,25 @@
-use std::io::{Error, ErrorKind::InvalidData, Result};
-
-use crate::{Request, Response};
+use crate::{
+    codec::{AsciiCodec, RtuCodec, TcpCodec},
+    error::Result,
+    util::lrc,
+    CanopenGeneralReferenceResponse, CustomResponse,
+    DiagnosticsResponse, Error, ExceptionResponse, Function,
+    GetCommEventCounterResponse, GetCommEventLogResponse, Head,
+    MaskWriteRegisterResponse, ReadDeviceIdentificationResponse,
+    ReadExceptionStatusResponse, ReadFifoQueueResponse,
+    ReadFileRecordResponse, ReadWriteMultipleRegistersResponse,
+    ReportServerIdResponse, Request, Response, Version,
+    WriteFileRecordResponse, MEI_READ_DEVICE_IDENTIFICATION
+};
 use bytes::{Buf, Bytes, BytesMut};
 use easy_modbus::{
-    codec::get_function, util::crc, ExceptionResponse, Function,
-    ReadCoilsResponse, ReadDiscreteInputsResponse,
+    util::crc, ReadCoilsResponse, ReadDiscreteInputsResponse,
     ReadInputRegistersResponse, ReadMultipleHoldingRegistersResponse,
     WriteMultipleCoilsResponse,
     WriteMultipleHoldingRegistersResponse, WriteSingleCoilResponse,
     WriteSingleHoldingRegisterResponse
 };
+use log::warn;
 use tokio_util::codec::Decoder;
 
 impl Decoder for Request {
@@ -20,80 +30,372 @@ impl Decoder for Request {
         &mut self,
         src: &mut BytesMut
     ) -> Result<Option<Response>> {
-        let Some(function) = src.get(1) else {
+        match self.head().version {
+            Version::Tcp => decode_tcp(self, src),
+            Version::Rtu => decode_rtu(self, src),
+            Version::Ascii => decode_ascii(self, src)
+        }
+    }
+}
+
+impl Decoder for TcpCodec {
+    type Error = Error;
+    type Item = Response;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut
+    ) -> Result<Option<Response>> {
+        let Some(request) = self.request.as_ref() else {
             return Ok(None);
         };
-        let (function, is_exception) = get_function(*function)?;
-        if !is_exception && function != self.head().function {
-            return Err(Error::new(
-                InvalidData,
-                format!(
-                    "Invalid function: {:0>2X} {:0>2X}",
-                    function.to_code(),
-                    self.head().function.to_code()
-                )
-            ));
+        let response = decode_tcp(request, src)?;
+        if response.is_some() {
+            self.request = None;
         }
+        Ok(response)
+    }
+}
 
-        let data_len: usize = {
-            if is_exception {
-                1
-            } else {
-                match function {
-                    Function::ReadCoils
-                    | Function::ReadDiscreteInputs
-                    | Function::ReadMultipleHoldingRegisters
-                    | Function::ReadInputRegisters => {
-                        src.get(2).map_or(0, |&bytes_num| {
-                            bytes_num as usize + 1
-                        })
-                    },
-                    Function::WriteSingleCoil
-                    | Function::WriteSingleHoldingRegister
-                    | Function::WriteMultipleCoils
-                    | Function::WriteMultipleHoldingRegisters => 4
-                }
-            }
+impl Decoder for RtuCodec {
+    type Error = Error;
+    type Item = Response;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut
+    ) -> Result<Option<Response>> {
+        let Some(request) = self.request.as_ref() else {
+            return Ok(None);
         };
-        let frame_len = data_len + 4;
-        if src.len() < frame_len {
+        let response = decode_rtu(request, src)?;
+        if response.is_some() {
+            self.request = None;
+        }
+        Ok(response)
+    }
+}
+
+impl Decoder for AsciiCodec {
+    type Error = Error;
+    type Item = Response;
+
+    fn decode(
+        &mut self,
+        src: &mut BytesMut
+    ) -> Result<Option<Response>> {
+        let Some(request) = self.request.as_ref() else {
             return Ok(None);
+        };
+        let response = decode_ascii(request, src)?;
+        if response.is_some() {
+            self.request = None;
         }
-        // addr + function + data
-        let body_bytes = src.copy_to_bytes(data_len + 2);
-        let crc = src.get_u16();
-        if crc::check(body_bytes.as_ref(), crc) {
-            let rs = get_response(
-                body_bytes.slice(2..),
-                self.clone(),
-                is_exception
+        Ok(response)
+    }
+}
+
+/// Max length of a RTU frame
+const RTU_MAX_LEN: usize = 256;
+/// Max value of the MBAP length field: unit id + PDU of 253 bytes
+const MBAP_MAX_LENGTH: usize = 254;
+/// Max length of an ASCII frame, ':' and CRLF included
+const ASCII_MAX_LEN: usize = 513;
+
+/// Decode a RTU frame: addr + function + data + crc
+fn decode_rtu(
+    request: &Request,
+    src: &mut BytesMut
+) -> Result<Option<Response>> {
+    let (Some(&uid), Some(&code)) = (src.first(), src.get(1)) else {
+        return Ok(None);
+    };
+    check_unit_id(request, uid)?;
+    let is_exception = parse_function(request, code)?;
+    check_byte_count(request, is_exception, &src[2..])?;
+
+    let Some(data_len) = data_len(request, is_exception, &src[2..])
+    else {
+        if src.len() >= RTU_MAX_LEN {
+            return Err(Error::FrameTooLong(src.len()));
+        }
+        return Ok(None);
+    };
+    let frame_len = data_len + 4;
+    if frame_len > RTU_MAX_LEN {
+        return Err(Error::FrameTooLong(frame_len));
+    }
+    if src.len() < frame_len {
+        return Ok(None);
+    }
+    // addr + function + data
+    let body_bytes = src.copy_to_bytes(data_len + 2);
+    let crc = src.get_u16();
+    if !crc::check(body_bytes.as_ref(), crc) {
+        return Err(Error::CrcMismatch {
+            expected: crc::compute(body_bytes.as_ref()),
+            actual:   crc
+        });
+    }
+    Ok(Some(get_response(
+        body_bytes.slice(2..),
+        request.clone(),
+        is_exception
+    )))
+}
+
+/// Decode a TCP frame: MBAP header + function + data
+///
+/// The MBAP length field counts the unit id, the function code and
+/// the data. Frames whose tid differs from the request are replies to
+/// earlier requests (e.g. timed out ones) and are dropped.
+fn decode_tcp(
+    request: &Request,
+    src: &mut BytesMut
+) -> Result<Option<Response>> {
+    loop {
+        if src.len() < Head::MBAP_LEN {
+            return Ok(None);
+        }
+        let pid = u16::from_be_bytes([src[2], src[3]]);
+        if pid != Head::PROTOCOL_ID {
+            return Err(Error::InvalidProtocolId(pid));
+        }
+        let length = u16::from_be_bytes([src[4], src[5]]) as usize;
+        if length < 2 {
+            return Err(Error::InvalidLength(length));
+        }
+        if length > MBAP_MAX_LENGTH {
+            return Err(Error::FrameTooLong(
+                Head::MBAP_LEN - 1 + length
+            ));
+        }
+        if src.len() < Head::MBAP_LEN - 1 + length {
+            return Ok(None);
+        }
+        let tid = src.get_u16();
+        // pid + length
+        src.advance(4);
+        let uid = src.get_u8();
+        let mut pdu = src.copy_to_bytes(length - 1);
+        if tid != request.head().tid {
+            warn!(
+                "Drop response of tid {}, expect {}",
+                tid,
+                request.head().tid
             );
-            return Ok(Some(rs));
+            continue;
+        }
+        check_unit_id(request, uid)?;
+        let is_exception = parse_function(request, pdu.get_u8())?;
+        if data_len(request, is_exception, &pdu) != Some(pdu.len()) {
+            return Err(Error::InvalidLength(pdu.len()));
         }
-        return Err(Error::new(
-            InvalidData,
-            format!(
-                "Invalid crc code: 0x{:0>2X}, {:?}",
-                crc, body_bytes
-            )
-        ));
+        return Ok(Some(get_response(
+            pdu,
+            request.clone(),
+            is_exception
+        )));
+    }
+}
+
+/// Decode an ASCII frame: ':' + hex(addr + function + data + lrc) +
+/// CRLF
+///
+/// Bytes before the ':' are noise and dropped.
+fn decode_ascii(
+    request: &Request,
+    src: &mut BytesMut
+) -> Result<Option<Response>> {
+    match src.iter().position(|byte| *byte == b':') {
+        Some(start) => src.advance(start),
+        None => {
+            src.clear();
+            return Ok(None);
+        }
+    }
+    let Some(end) = src.windows(2).position(|w| w == b"\r\n") else {
+        if src.len() > ASCII_MAX_LEN {
+            return Err(Error::FrameTooLong(src.len()));
+        }
+        return Ok(None);
+    };
+    let line = src.split_to(end + 2);
+    let mut frame = decode_hex(&line[1..end])?;
+    if frame.len() < 4 {
+        return Err(Error::InvalidLength(frame.len()));
+    }
+    let actual = frame.pop().unwrap_or_default();
+    let expected = lrc(&frame);
+    if actual != expected {
+        return Err(Error::LrcMismatch { expected, actual });
+    }
+    check_unit_id(request, frame[0])?;
+    let is_exception = parse_function(request, frame[1])?;
+    let data = &frame[2..];
+    if data_len(request, is_exception, data) != Some(data.len()) {
+        return Err(Error::InvalidLength(data.len()));
+    }
+    Ok(Some(get_response(
+        Bytes::copy_from_slice(data),
+        request.clone(),
+        is_exception
+    )))
+}
+
+fn decode_hex(hex: &[u8]) -> Result<Vec<u8>> {
+    hex.chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|digits| {
+                    digits.len() == 2
+                        && digits
+                            .bytes()
+                            .all(|b| b.is_ascii_hexdigit())
+                })
+                .and_then(|digits| {
+                    u8::from_str_radix(digits, 16).ok()
+                })
+                .ok_or(Error::InvalidAscii)
+        })
+        .collect()
+}
+
+/// Length of the data following the function code of the response
+/// to `request`, `data` being the bytes received so far. `None` if it
+/// isn't known yet.
+fn data_len(
+    request: &Request,
+    is_exception: bool,
+    data: &[u8]
+) -> Option<usize> {
+    if is_exception {
+        return Some(1);
+    }
+    match request.head().function {
+        Function::ReadCoils
+        | Function::ReadDiscreteInputs
+        | Function::ReadMultipleHoldingRegisters
+        | Function::ReadInputRegisters
+        | Function::ReadWriteMultipleRegisters
+        | Function::ReportServerId
+        | Function::ReadFileRecord
+        | Function::WriteFileRecord => {
+            data.first().map(|&bytes_num| bytes_num as usize + 1)
+        },
+        Function::WriteSingleCoil
+        | Function::WriteSingleHoldingRegister
+        | Function::WriteMultipleCoils
+        | Function::WriteMultipleHoldingRegisters => Some(4),
+        Function::ReadExceptionStatus => Some(1),
+        Function::Diagnostics => match request {
+            // echo of the request
+            Request::Diagnostics(_, body) => {
+                Some(2 + body.data.len())
+            },
+            _ => None
+        },
+        Function::GetCommEventCounter => Some(4),
+        // status, event count and message count at least
+        Function::GetCommEventLog => data
+            .first()
+            .map(|&bytes_num| bytes_num.max(6) as usize + 1),
+        // two bytes byte count, the FIFO count at least
+        Function::ReadFifoQueue => match data {
+            [high, low, ..] => {
+                let bytes_num = u16::from_be_bytes([*high, *low]);
+                Some(bytes_num.max(2) as usize + 2)
+            },
+            _ => None
+        },
+        Function::MaskWriteRegister => Some(6),
+        Function::EncapsulatedInterfaceTransport => match request {
+            // the client checks the MEI type of the response
+            Request::CanopenGeneralReference(_, body) => body
+                .response_len
+                .response_len(&body.payload, data.get(1..)?)
+                .map(|len| len + 1),
+            _ => match data.first() {
+                Some(&MEI_READ_DEVICE_IDENTIFICATION) => {
+                    device_identification_len(data)
+                },
+                _ => None
+            }
+        },
+        Function::Custom(_) => match request {
+            Request::Custom(_, body) => {
+                body.response_len.response_len(&body.payload, data)
+            },
+            _ => None
+        }
+    }
+}
+
+/// Fail on a byte count below the fixed fields of the response as
+/// soon as it's received, instead of waiting for bytes which never
+/// arrive
+fn check_byte_count(
+    request: &Request,
+    is_exception: bool,
+    data: &[u8]
+) -> Result<()> {
+    if is_exception {
+        return Ok(());
+    }
+    let (bytes_num, min) = match (request.head().function, data) {
+        // status, event count and message count
+        (Function::GetCommEventLog, [bytes_num, ..]) => {
+            (*bytes_num as usize, 6)
+        },
+        // FIFO count
+        (Function::ReadFifoQueue, [high, low, ..]) => {
+            (u16::from_be_bytes([*high, *low]) as usize, 2)
+        },
+        _ => return Ok(())
+    };
+    if bytes_num < min {
+        return Err(Error::InvalidLength(bytes_num));
+    }
+    Ok(())
+}
+
+/// Length of a read device identification response: MEI type,
+/// access type, conformity level, more follows, next object id,
+/// number of objects and the objects (id, length, value)
+fn device_identification_len(data: &[u8]) -> Option<usize> {
+    let objects_number = *data.get(5)?;
+    let mut len = 6;
+    for _ in 0..objects_number {
+        len += 2 + *data.get(len + 1)? as usize;
+    }
+    Some(len)
+}
+
+fn check_unit_id(request: &Request, uid: u8) -> Result<()> {
+    let expected = request.head().uid;
+    if uid != expected {
+        return Err(Error::UnexpectedUnitId {
+            expected,
+            actual: uid
+        });
     }
+    Ok(())
+}
+
+/// Parse the function code of a response, which must be the one of
+/// the request, with the exception bit set for exception responses.
+/// Returns whether it is an exception response.
+fn parse_function(request: &Request, code: u8) -> Result<bool> {
+    let expected = request.head().function.to_code();
+    if code & 0x7F != expected {
+        return Err(Error::UnexpectedFunction {
+            expected,
+            actual: code
+        });
+    }
+    Ok(code & 0x80 != 0)
 }
-//
-// impl Decoder for TcpCodec {
-//     type Item = Response;
-//     type Error = Error;
-//
-//     fn decode(&mut self, src: &mut BytesMut) ->
-// Result<Option<Response>> {         if src.len() < 4 {
-//             return Ok(None);
-//         }
-//         let head = Head::tcp_try_from(src.copy_to_bytes(8))?;
-//         let len = head.length as usize - 2;
-//         let response = get_response(src.copy_to_bytes(len), head);
-//         Ok(Some(response))
-//     }
-// }
 
 fn get_response(
     src: Bytes,
@@ -164,6 +466,110 @@ fn get_response(
                 Ok(WriteMultipleHoldingRegistersResponse::from(src))
             };
             Response::WriteMultipleHoldingRegisters(head, req, rs)
+        },
+        Request::MaskWriteRegister(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(MaskWriteRegisterResponse::from(src))
+            };
+            Response::MaskWriteRegister(head, req, rs)
+        },
+        Request::ReadWriteMultipleRegisters(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(ReadWriteMultipleRegistersResponse::from(src))
+            };
+            Response::ReadWriteMultipleRegisters(head, req, rs)
+        },
+        Request::ReadDeviceIdentification(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(ReadDeviceIdentificationResponse::from(src))
+            };
+            Response::ReadDeviceIdentification(head, req, rs)
+        },
+        Request::ReadExceptionStatus(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(ReadExceptionStatusResponse::from(src))
+            };
+            Response::ReadExceptionStatus(head, req, rs)
+        },
+        Request::Diagnostics(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(DiagnosticsResponse::from(src))
+            };
+            Response::Diagnostics(head, req, rs)
+        },
+        Request::GetCommEventCounter(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(GetCommEventCounterResponse::from(src))
+            };
+            Response::GetCommEventCounter(head, req, rs)
+        },
+        Request::GetCommEventLog(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(GetCommEventLogResponse::from(src))
+            };
+            Response::GetCommEventLog(head, req, rs)
+        },
+        Request::ReportServerId(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(ReportServerIdResponse::from(src))
+            };
+            Response::ReportServerId(head, req, rs)
+        },
+        Request::ReadFileRecord(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(ReadFileRecordResponse::from(src))
+            };
+            Response::ReadFileRecord(head, req, rs)
+        },
+        Request::WriteFileRecord(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(WriteFileRecordResponse::from(src))
+            };
+            Response::WriteFileRecord(head, req, rs)
+        },
+        Request::ReadFifoQueue(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(ReadFifoQueueResponse::from(src))
+            };
+            Response::ReadFifoQueue(head, req, rs)
+        },
+        Request::Custom(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(CustomResponse::from(src))
+            };
+            Response::Custom(head, req, rs)
+        },
+        Request::CanopenGeneralReference(head, req) => {
+            let rs = if is_exception {
+                Err(ExceptionResponse::from(src))
+            } else {
+                Ok(CanopenGeneralReferenceResponse::from(src))
+            };
+            Response::CanopenGeneralReference(head, req, rs)
         }
     }
 }
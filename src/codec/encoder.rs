@@ -1,10 +1,15 @@
-use std::io::Error;
-
 use bytes::{BufMut, BytesMut};
-use easy_modbus::{util::crc, Version::Rtu};
+use easy_modbus::util::crc;
 use tokio_util::codec::Encoder;
 
-use crate::Request;
+use crate::{
+    codec::{AsciiCodec, RtuCodec, TcpCodec},
+    error::Result,
+    frame::records_in_file,
+    util::lrc,
+    Error, Function, ReadWriteMultipleRegistersRequest, Request,
+    Version, FILE_RECORD_MAX_BYTES
+};
 
 impl Encoder<()> for Request {
     type Error = Error;
@@ -13,57 +18,217 @@ impl Encoder<()> for Request {
         &mut self,
         _item: (),
         dst: &mut BytesMut
-    ) -> std::result::Result<(), Self::Error> {
-        request_to_bytesmut(&self, dst);
+    ) -> Result<()> {
+        request_to_bytesmut(self, dst);
+        Ok(())
+    }
+}
+
+impl Encoder<Request> for TcpCodec {
+    type Error = Error;
+
+    fn encode(
+        &mut self,
+        item: Request,
+        dst: &mut BytesMut
+    ) -> Result<()> {
+        check_quantity(&item)?;
+        let mut item = item;
+        let head = item.head_mut();
+        head.version = Version::Tcp;
+        head.tid = self.tids.next(head.uid);
+        request_to_bytesmut(&item, dst);
+        self.request = Some(item);
+        Ok(())
+    }
+}
+
+impl Encoder<Request> for AsciiCodec {
+    type Error = Error;
+
+    fn encode(
+        &mut self,
+        item: Request,
+        dst: &mut BytesMut
+    ) -> Result<()> {
+        let item = item.with_version(Version::Ascii);
+        check_quantity(&item)?;
+        request_to_bytesmut(&item, dst);
+        self.request = Some(item);
+        Ok(())
+    }
+}
+
+impl Encoder<Request> for RtuCodec {
+    type Error = Error;
+
+    fn encode(
+        &mut self,
+        item: Request,
+        dst: &mut BytesMut
+    ) -> Result<()> {
+        let item = item.with_version(Version::Rtu);
+        check_quantity(&item)?;
+        request_to_bytesmut(&item, dst);
+        self.request = Some(item);
         Ok(())
     }
 }
 
 pub fn request_to_bytesmut(item: &Request, dst: &mut BytesMut) {
-    let version;
-    match item {
-        Request::ReadCoils(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::ReadDiscreteInputs(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::ReadMultipleHoldingRegisters(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::ReadInputRegisters(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::WriteSingleCoil(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::WriteSingleHoldingRegister(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::WriteMultipleCoils(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        },
-        Request::WriteMultipleHoldingRegisters(head, body) => {
-            version = head.version.clone();
-            dst.put(BytesMut::from(head.clone()));
-            dst.put(BytesMut::from(body.clone()));
-        }
+    let body = match item {
+        Request::ReadCoils(_, body) => BytesMut::from(body.clone()),
+        Request::ReadDiscreteInputs(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadMultipleHoldingRegisters(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadInputRegisters(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::WriteSingleCoil(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::WriteSingleHoldingRegister(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::WriteMultipleCoils(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::WriteMultipleHoldingRegisters(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::MaskWriteRegister(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadWriteMultipleRegisters(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadDeviceIdentification(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadExceptionStatus(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::Diagnostics(_, body) => BytesMut::from(body.clone()),
+        Request::GetCommEventCounter(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::GetCommEventLog(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReportServerId(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadFileRecord(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::WriteFileRecord(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::ReadFifoQueue(_, body) => {
+            BytesMut::from(body.clone())
+        },
+        Request::Custom(_, body) => BytesMut::from(body.clone()),
+        Request::CanopenGeneralReference(_, body) => {
+            BytesMut::from(body.clone())
+        },
     };
-    if Rtu == version {
-        dst.put_u16(crc::compute(&dst.to_vec()));
+    let head = item.head();
+    let start = dst.len();
+    head.to_bytes(body.len(), dst);
+    dst.put(body);
+    match head.version {
+        Version::Tcp => {},
+        Version::Rtu => {
+            let crc = crc::compute(&dst[start..]);
+            dst.put_u16(crc);
+        },
+        Version::Ascii => {
+            let frame = dst.split_off(start);
+            let lrc = lrc(&frame);
+            dst.put_u8(b':');
+            for byte in frame.iter().chain([lrc].iter()) {
+                dst.put_u8(HEX_DIGITS[(byte >> 4) as usize]);
+                dst.put_u8(HEX_DIGITS[(byte & 0x0F) as usize]);
+            }
+            dst.put_slice(b"\r\n");
+        }
+    }
+}
+
+/// Reject requests whose quantities exceed the limits of their
+/// function, the server would answer with an exception
+fn check_quantity(item: &Request) -> Result<()> {
+    match item {
+        Request::ReadWriteMultipleRegisters(_, body) => {
+            let read = body.read_quantity;
+            if read == 0
+                || read
+                    > ReadWriteMultipleRegistersRequest::MAX_READ_QUANTITY
+            {
+                return Err(Error::InvalidQuantity(read));
+            }
+            let write = body.write_quantity;
+            if write == 0
+                || write
+                    > ReadWriteMultipleRegistersRequest::MAX_WRITE_QUANTITY
+            {
+                return Err(Error::InvalidQuantity(write));
+            }
+            // two bytes per register, odd values can't be encoded
+            if body.bytes_number as usize != body.values.len()
+                || body.bytes_number as u16 != write * 2
+            {
+                return Err(Error::InvalidQuantity(write));
+            }
+        },
+        Request::ReadFileRecord(_, body)
+            if body.sub_requests.is_empty()
+                || body.bytes_number() > FILE_RECORD_MAX_BYTES
+                || body.response_bytes_number()
+                    > FILE_RECORD_MAX_BYTES
+                || !body.sub_requests.iter().all(|sub| {
+                    records_in_file(
+                        sub.record_number,
+                        sub.record_length as usize
+                    )
+                }) =>
+        {
+            let records =
+                body.sub_requests.iter().map(|sub| sub.record_length);
+            return Err(Error::InvalidQuantity(
+                records.fold(0, u16::saturating_add)
+            ));
+        },
+        Request::WriteFileRecord(_, body)
+            if body.records.is_empty()
+                || body.bytes_number() > FILE_RECORD_MAX_BYTES
+                || !body.records.iter().all(|record| {
+                    records_in_file(
+                        record.record_number,
+                        record.values.len()
+                    )
+                }) =>
+        {
+            let records = body
+                .records
+                .iter()
+                .map(|record| record.values.len() as u16);
+            return Err(Error::InvalidQuantity(
+                records.fold(0, u16::saturating_add)
+            ));
+        },
+        // a public function code would be decoded as that function
+        Request::Custom(_, body)
+            if !Function::is_user_defined(body.function) =>
+        {
+            return Err(Error::InvalidFunction(body.function));
+        },
+        _ => {}
     }
+    Ok(())
 }
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
@@ -1,14 +1,570 @@
 //! Codec based [tokio-util](https://docs.rs/tokio-util/latest/tokio_util/codec/index.html)
+//!
+//! The codecs remember the last encoded request, so the response can
+//! be decoded against it.
+
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{Error, Request, Response, TidAllocator, Version};
 
 mod decoder;
 mod encoder;
 
 /// Mutual convert TCP Client frames and buffers.
-#[derive(Debug, Default)]
-pub struct TcpCodec;
+///
+/// Frames carry a MBAP header, the length of a response is read from
+/// it. Transaction ids are allocated per codec, i.e. per connection,
+/// and responses with another tid are dropped.
+#[derive(Default)]
+pub struct TcpCodec {
+    request: Option<Request>,
+    tids:    TidAllocator
+}
 
 /// Mutual convert RTU Client frames and buffers.
-#[derive(Debug, Default)]
-pub struct RtuCodec;
+///
+/// Frames end with a CRC, the length of a response is derived from
+/// the function code of the request.
+#[derive(Default)]
+pub struct RtuCodec {
+    request: Option<Request>
+}
+
+/// Mutual convert ASCII Client frames and buffers.
+///
+/// Frames start with ':', carry the hex encoded unit id, PDU and LRC
+/// and end with CRLF.
+#[derive(Default)]
+pub struct AsciiCodec {
+    request: Option<Request>
+}
+
+/// Codec usable by the `Client`
+pub trait ClientCodec:
+    Encoder<Request, Error = Error>
+    + Decoder<Item = Response, Error = Error> {
+    /// Framing of the codec
+    fn version(&self) -> Version;
+}
+
+impl ClientCodec for TcpCodec {
+    fn version(&self) -> Version {
+        Version::Tcp
+    }
+}
+
+impl ClientCodec for RtuCodec {
+    fn version(&self) -> Version {
+        Version::Rtu
+    }
+}
+
+impl ClientCodec for AsciiCodec {
+    fn version(&self) -> Version {
+        Version::Ascii
+    }
+}
 
 pub use encoder::*;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate::{
+        CustomRequest, FileRecord, FileSubRequest, Function, Head
+    };
+
+    fn read_holding_registers<C: ClientCodec>(
+        codec: &mut C
+    ) -> BytesMut {
+        let request =
+            Request::read_multiple_holding_registers_request(
+                0x01, 0x0010, 2
+            );
+        let mut dst = BytesMut::new();
+        codec.encode(request, &mut dst).unwrap();
+        dst
+    }
+
+    #[test]
+    fn tcp_round_trip() {
+        let mut codec = TcpCodec::default();
+        let dst = read_holding_registers(&mut codec);
+        assert_eq!(
+            &dst[..],
+            [
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00,
+                0x10, 0x00, 0x02
+            ]
+        );
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04,
+                0x00, 0x0A, 0x00, 0x0B
+            ][..]
+        );
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(response.head().tid, 1);
+        assert_eq!(response.registers(), Some(vec![0x000A, 0x000B]));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn tcp_drop_stale_tid() {
+        let mut codec = TcpCodec::default();
+        // the first request timed out, its reply arrives late
+        read_holding_registers(&mut codec);
+        let dst = read_holding_registers(&mut codec);
+        assert_eq!(&dst[..2], [0x00, 0x02]);
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04,
+                0x00, 0x01, 0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00,
+                0x07, 0x01, 0x03, 0x04, 0x00, 0x0A, 0x00, 0x0B
+            ][..]
+        );
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(response.head().tid, 2);
+        assert_eq!(response.registers(), Some(vec![0x000A, 0x000B]));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn tcp_exception_round_trip() {
+        let mut codec = TcpCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(
+            &[0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x83, 0x02][..]
+        );
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert!(matches!(
+            response.into_result(),
+            Err(Error::Exception(
+                crate::Exception::IllegalDataAddress
+            ))
+        ));
+    }
+
+    #[test]
+    fn tcp_invalid_protocol_id() {
+        let mut codec = TcpCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x01, 0x00, 0x01, 0x00, 0x07, 0x01, 0x03, 0x04,
+                0x00, 0x0A, 0x00, 0x0B
+            ][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidProtocolId(0x0001))
+        ));
+    }
+
+    #[test]
+    fn tcp_invalid_length() {
+        let mut codec = TcpCodec::default();
+        read_holding_registers(&mut codec);
+        // length field too short for unit id + function
+        let mut src = BytesMut::from(
+            &[0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(1))
+        ));
+        // length field disagrees with the byte count
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x04,
+                0x00, 0x0A, 0x00
+            ][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(4))
+        ));
+        // length field above the maximum PDU
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(
+            &[0x00, 0x03, 0x00, 0x00, 0x01, 0x00, 0x01][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::FrameTooLong(262))
+        ));
+    }
+
+    #[test]
+    fn tcp_split_frame() {
+        let mut codec = TcpCodec::default();
+        read_holding_registers(&mut codec);
+        let frame = [
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04,
+            0x00, 0x0A, 0x00, 0x0B
+        ];
+        // first read ends within the MBAP header, second within the
+        // PDU
+        let mut src = BytesMut::from(&frame[..4]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&frame[4..9]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&frame[9..]);
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(response.registers(), Some(vec![0x000A, 0x000B]));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn rtu_round_trip() {
+        let mut codec = RtuCodec::default();
+        let dst = read_holding_registers(&mut codec);
+        assert_eq!(
+            &dst[..],
+            [0x01, 0x03, 0x00, 0x10, 0x00, 0x02, 0xC5, 0xCE]
+        );
+        let mut src = BytesMut::from(
+            &[0x01, 0x03, 0x04, 0x00, 0x0A, 0x00, 0x0B, 0x9B, 0xF6][..]
+        );
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(response.registers(), Some(vec![0x000A, 0x000B]));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn rtu_crc_mismatch() {
+        let mut codec = RtuCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(
+            &[0x01, 0x03, 0x04, 0x00, 0x0A, 0x00, 0x0C, 0x9B, 0xF6][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::CrcMismatch { actual: 0x9BF6, .. })
+        ));
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        let mut codec = AsciiCodec::default();
+        let dst = read_holding_registers(&mut codec);
+        // LRC: -(0x01 + 0x03 + 0x10 + 0x02) = 0xEA
+        assert_eq!(&dst[..], b":010300100002EA\r\n");
+        // noise before the ':' is dropped
+        let mut src = BytesMut::from(&b"\0:010304000A000BE3\r\n"[..]);
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(response.registers(), Some(vec![0x000A, 0x000B]));
+        assert!(src.is_empty());
+    }
+
+    #[test]
+    fn ascii_lowercase_hex() {
+        let mut codec = AsciiCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(&b":010304000a000be3\r\n"[..]);
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(response.registers(), Some(vec![0x000A, 0x000B]));
+    }
+
+    #[test]
+    fn ascii_lrc_mismatch() {
+        let mut codec = AsciiCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(&b":010304000A000BE4\r\n"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::LrcMismatch {
+                expected: 0xE3,
+                actual:   0xE4
+            })
+        ));
+    }
+
+    #[test]
+    fn ascii_invalid_hex() {
+        let mut codec = AsciiCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(&b":010304000G000BE3\r\n"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidAscii)
+        ));
+        // odd number of hex digits
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(&b":010304000A000BE\r\n"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidAscii)
+        ));
+    }
+
+    #[test]
+    fn ascii_missing_crlf() {
+        let mut codec = AsciiCodec::default();
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(&b":010304000A000BE3"[..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        // the frame completes with the next read
+        src.extend_from_slice(b"\r\n");
+        assert!(codec.decode(&mut src).unwrap().is_some());
+        // a frame never terminated exceeds the maximum length
+        read_holding_registers(&mut codec);
+        let mut src = BytesMut::from(&[b':'; 600][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::FrameTooLong(600))
+        ));
+    }
+
+    #[test]
+    fn read_write_multiple_odd_values() {
+        let mut codec = TcpCodec::default();
+        let mut dst = BytesMut::new();
+        let request = Request::read_write_multiple_registers_request(
+            0x01,
+            0x0000,
+            2,
+            0x0010,
+            vec![0x00, 0x01, 0x00]
+        );
+        assert!(matches!(
+            codec.encode(request, &mut dst),
+            Err(Error::InvalidQuantity(1))
+        ));
+        assert!(dst.is_empty());
+        let request = Request::read_write_multiple_registers_request(
+            0x01,
+            0x0000,
+            2,
+            0x0010,
+            vec![0x00, 0x01, 0x00, 0x02]
+        );
+        codec.encode(request, &mut dst).unwrap();
+        assert_eq!(
+            &dst[14..],
+            [0x00, 0x02, 0x04, 0x00, 0x01, 0x00, 0x02]
+        );
+    }
+
+    #[test]
+    fn comm_event_log_short_byte_count() {
+        let mut codec = TcpCodec::default();
+        let request = Request::get_comm_event_log_request(0x01);
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        // status, event count and message count are missing
+        let mut src = BytesMut::from(
+            &[0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x0C, 0x00][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(1))
+        ));
+
+        let mut codec = RtuCodec::default();
+        let request = Request::get_comm_event_log_request(0x01);
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        // fails as soon as the byte count is received
+        let mut src = BytesMut::from(&[0x01, 0x0C, 0x00][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(0))
+        ));
+        let mut codec = RtuCodec::default();
+        let request = Request::get_comm_event_log_request(0x01);
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        let mut src = BytesMut::from(&[0x01, 0x0C, 0x05][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(5))
+        ));
+
+        let mut codec = AsciiCodec::default();
+        let request = Request::get_comm_event_log_request(0x01);
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        let mut src = BytesMut::from(&b":010C00F3\r\n"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(1))
+        ));
+    }
+
+    #[test]
+    fn mask_write_register_round_trip() {
+        let request = Request::mask_write_register_request(
+            0x01, 0x0004, 0x00F2, 0x0025
+        );
+        let mut codec = RtuCodec::default();
+        let mut dst = BytesMut::new();
+        codec.encode(request.clone(), &mut dst).unwrap();
+        assert_eq!(
+            &dst[..8],
+            [0x01, 0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25]
+        );
+        let crc = easy_modbus::util::crc::compute(&dst[..8]);
+        assert_eq!(&dst[8..], crc.to_be_bytes());
+        // echo of the request
+        let mut src = dst.clone();
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        let Response::MaskWriteRegister(_, _, Ok(rs)) = response
+        else {
+            panic!("unexpected response");
+        };
+        assert_eq!(
+            (rs.address, rs.and_mask, rs.or_mask),
+            (0x0004, 0x00F2, 0x0025)
+        );
+
+        let mut codec = TcpCodec::default();
+        let mut dst = BytesMut::new();
+        codec.encode(request, &mut dst).unwrap();
+        assert_eq!(
+            &dst[..],
+            [
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x01, 0x16, 0x00,
+                0x04, 0x00, 0xF2, 0x00, 0x25
+            ]
+        );
+        // the echo has a fixed length
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x16, 0x00,
+                0x04, 0x00, 0xF2
+            ][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(4))
+        ));
+    }
+
+    #[test]
+    fn fifo_queue_round_trip() {
+        let mut codec = RtuCodec::default();
+        let mut dst = BytesMut::new();
+        let request = Request::read_fifo_queue_request(0x01, 0x04DE);
+        codec.encode(request, &mut dst).unwrap();
+        assert_eq!(&dst[..4], [0x01, 0x18, 0x04, 0xDE]);
+        // two bytes byte count: FIFO count and two values
+        let frame = [
+            0x01, 0x18, 0x00, 0x06, 0x00, 0x02, 0x01, 0xB8, 0x12,
+            0x84
+        ];
+        let crc = easy_modbus::util::crc::compute(&frame);
+        let mut src = BytesMut::from(&frame[..3]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&frame[3..]);
+        assert!(codec.decode(&mut src).unwrap().is_none());
+        src.extend_from_slice(&crc.to_be_bytes());
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        let Response::ReadFifoQueue(_, _, Ok(rs)) = response else {
+            panic!("unexpected response");
+        };
+        assert_eq!(rs.bytes_number, 6);
+        assert_eq!(rs.fifo_count, 2);
+        assert_eq!(rs.values, [0x01, 0xB8, 0x12, 0x84]);
+    }
+
+    #[test]
+    fn fifo_queue_invalid_byte_count() {
+        let mut codec = RtuCodec::default();
+        let request = Request::read_fifo_queue_request(0x01, 0x04DE);
+        codec.encode(request.clone(), &mut BytesMut::new()).unwrap();
+        // fails as soon as the byte count is received
+        let mut src = BytesMut::from(&[0x01, 0x18, 0x00, 0x01][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(1))
+        ));
+        // the high byte counts as well
+        codec.encode(request.clone(), &mut BytesMut::new()).unwrap();
+        let mut src = BytesMut::from(&[0x01, 0x18, 0x01, 0x00][..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::FrameTooLong(262))
+        ));
+
+        let mut codec = TcpCodec::default();
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x01, 0x18, 0x00,
+                0x01, 0x00
+            ][..]
+        );
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(Error::InvalidLength(3))
+        ));
+    }
+
+    #[test]
+    fn file_records_past_last_record() {
+        let mut codec = TcpCodec::default();
+        let mut dst = BytesMut::new();
+        let sub_request = FileSubRequest::new(0x0004, 0x270F, 2);
+        let request = Request::read_file_record_request(
+            0x01,
+            vec![sub_request]
+        );
+        assert!(matches!(
+            codec.encode(request, &mut dst),
+            Err(Error::InvalidQuantity(2))
+        ));
+        let record = FileRecord::new(0x0004, 0x2710, vec![0x0001]);
+        let request =
+            Request::write_file_record_request(0x01, vec![record]);
+        assert!(matches!(
+            codec.encode(request, &mut dst),
+            Err(Error::InvalidQuantity(1))
+        ));
+        assert!(dst.is_empty());
+        let sub_request = FileSubRequest::new(0x0004, 0x270F, 1);
+        let request = Request::read_file_record_request(
+            0x01,
+            vec![sub_request]
+        );
+        codec.encode(request, &mut dst).unwrap();
+    }
+
+    #[test]
+    fn custom_function_codes() {
+        let response_len = |_: &[u8], _: &[u8]| Some(2);
+        for code in [0x03, 0x2B, 0x40, 0x49, 0x63, 0x6F, 0x80, 0xC1] {
+            assert!(matches!(
+                Request::custom_request(0x01, code, vec![], response_len),
+                Err(Error::InvalidFunction(c)) if c == code
+            ));
+        }
+        let mut codec = RtuCodec::default();
+        let mut dst = BytesMut::new();
+        // bodies can still be built by hand
+        let request = Request::Custom(
+            Head::new(
+                0,
+                0x01,
+                Function::Custom(0x03),
+                Version::Rtu,
+                false
+            ),
+            CustomRequest::new(0x03, vec![], Arc::new(response_len))
+        );
+        assert!(matches!(
+            codec.encode(request, &mut dst),
+            Err(Error::InvalidFunction(0x03))
+        ));
+        let request = Request::custom_request(
+            0x01,
+            0x41,
+            vec![0x00],
+            response_len
+        )
+        .unwrap();
+        codec.encode(request, &mut dst).unwrap();
+        assert_eq!(&dst[..3], [0x01, 0x41, 0x00]);
+    }
+}
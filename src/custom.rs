@@ -0,0 +1,108 @@
+//! User defined function codes
+//!
+//! The decoders know the response length of the public function
+//! codes. For a user defined function code (65 to 72, 100 to 110)
+//! the request carries a `CustomFunction` which computes it.
+
+use std::{fmt, sync::Arc};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Response length of a user defined function code
+///
+/// Implemented for closures taking the payload of the request and
+/// the response data received so far.
+///
+/// # Examples
+///
+/// ```
+/// use modbus_client::Request;
+///
+/// // byte count + values
+/// let request = Request::custom_request(
+///     0x01,
+///     0x41,
+///     vec![0x00, 0x10],
+///     |_: &[u8], data: &[u8]| {
+///         data.first().map(|&bytes_num| bytes_num as usize + 1)
+///     }
+/// )
+/// .unwrap();
+/// ```
+pub trait CustomFunction: Send + Sync {
+    /// Length of the response data following the function code,
+    /// `data` being the bytes received so far. `None` if it isn't
+    /// known yet.
+    fn response_len(
+        &self,
+        payload: &[u8],
+        data: &[u8]
+    ) -> Option<usize>;
+}
+
+impl<F> CustomFunction for F
+where
+    F: Fn(&[u8], &[u8]) -> Option<usize> + Send + Sync
+{
+    fn response_len(
+        &self,
+        payload: &[u8],
+        data: &[u8]
+    ) -> Option<usize> {
+        self(payload, data)
+    }
+}
+
+/// Request body of a user defined function code
+#[derive(Clone)]
+pub struct CustomRequest {
+    pub function:     u8,
+    pub payload:      Vec<u8>,
+    pub response_len: Arc<dyn CustomFunction>
+}
+
+impl CustomRequest {
+    pub fn new(
+        function: u8,
+        payload: Vec<u8>,
+        response_len: Arc<dyn CustomFunction>
+    ) -> Self {
+        Self {
+            function,
+            payload,
+            response_len
+        }
+    }
+}
+
+impl fmt::Debug for CustomRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomRequest")
+            .field("function", &self.function)
+            .field("payload", &self.payload)
+            .finish()
+    }
+}
+
+impl From<CustomRequest> for BytesMut {
+    fn from(request: CustomRequest) -> Self {
+        let mut buf = BytesMut::with_capacity(request.payload.len());
+        buf.put_slice(&request.payload);
+        buf
+    }
+}
+
+/// Response body of a user defined function code, the data following
+/// the function code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomResponse {
+    pub payload: Vec<u8>
+}
+
+impl From<Bytes> for CustomResponse {
+    fn from(src: Bytes) -> Self {
+        Self {
+            payload: src.to_vec()
+        }
+    }
+}
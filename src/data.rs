@@ -0,0 +1,594 @@
+//! Values spread across several registers
+//!
+//! Modbus only defines 16 bit registers, wider values are stored in
+//! consecutive registers in an order which differs between vendors.
+//! The order is named after the bytes of the big-endian value, e.g.
+//! `0x11223344` (A = 0x11, B = 0x22, C = 0x33, D = 0x44) is stored as
+//! `[0x3344, 0x1122]` by `WordOrder::Cdab`.
+//!
+//! # Examples
+//!
+//! ```
+//! use modbus_client::data::{self, WordOrder};
+//!
+//! let registers = data::encode(&[1.5f32], WordOrder::Cdab);
+//! assert_eq!(registers, vec![0x0000, 0x3FC0]);
+//! let values: Vec<f32> = data::decode(&registers, WordOrder::Cdab)
+//!     .unwrap();
+//! assert_eq!(values, vec![1.5]);
+//! ```
+//!
+//! Strings are described by a `StringFormat`, packed BCD values are
+//! converted by `decode_bcd` and `encode_bcd`. Register bytes, e.g.
+//! the payload of
+//! `Request::write_multiple_holding_registers_request`, are converted
+//! by `registers_to_bytes` and `bytes_to_registers`.
+//!
+//! ```
+//! use modbus_client::{
+//!     data::{self, StringFormat},
+//!     Request
+//! };
+//!
+//! let tag = StringFormat::new(8).encode("PUMP-01").unwrap();
+//! let request = Request::write_multiple_holding_registers_request(
+//!     0x01,
+//!     0x0100,
+//!     data::registers_to_bytes(&tag)
+//! );
+//! ```
+
+pub use crate::util::{bytes_to_registers, registers_to_bytes};
+use crate::{Request, Response};
+
+/// Order of the bytes of a value in its registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "UPPERCASE")
+)]
+pub enum WordOrder {
+    /// Big-endian, most significant register first (modbus default)
+    #[default]
+    Abcd,
+    /// Least significant register first, big-endian registers
+    Cdab,
+    /// Most significant register first, bytes of each register
+    /// swapped
+    Badc,
+    /// Little-endian, least significant register first
+    Dcba
+}
+
+impl WordOrder {
+    /// Reorder big-endian bytes into register bytes and back
+    fn apply(self, bytes: &mut [u8]) {
+        match self {
+            WordOrder::Abcd => {},
+            WordOrder::Cdab => {
+                bytes.reverse();
+                swap_bytes(bytes);
+            },
+            WordOrder::Badc => swap_bytes(bytes),
+            WordOrder::Dcba => bytes.reverse()
+        }
+    }
+}
+
+/// Swap the bytes of each register
+fn swap_bytes(bytes: &mut [u8]) {
+    for pair in bytes.chunks_exact_mut(2) {
+        pair.swap(0, 1);
+    }
+}
+
+/// Value stored in `REGISTERS` consecutive registers
+pub trait RegisterValue: Sized {
+    /// Number of registers of a value
+    const REGISTERS: usize;
+
+    /// Decode a value from exactly `REGISTERS` registers
+    fn from_registers(
+        registers: &[u16],
+        order: WordOrder
+    ) -> Option<Self>;
+
+    /// Encode the value into `REGISTERS` registers
+    fn to_registers(&self, order: WordOrder) -> Vec<u16>;
+}
+
+macro_rules! register_value {
+    ($($ty:ty),*) => {$(
+        impl RegisterValue for $ty {
+            const REGISTERS: usize = std::mem::size_of::<$ty>() / 2;
+
+            fn from_registers(
+                registers: &[u16],
+                order: WordOrder
+            ) -> Option<Self> {
+                if registers.len() != Self::REGISTERS {
+                    return None;
+                }
+                let mut bytes = registers_to_bytes(registers);
+                order.apply(&mut bytes);
+                Some(<$ty>::from_be_bytes(bytes.try_into().ok()?))
+            }
+
+            fn to_registers(&self, order: WordOrder) -> Vec<u16> {
+                let mut bytes = self.to_be_bytes();
+                order.apply(&mut bytes);
+                bytes_to_registers(&bytes)
+            }
+        }
+    )*};
+}
+
+register_value!(u16, i16, u32, i32, f32, u64, i64, f64);
+
+/// Struct stored in a block of consecutive holding registers,
+/// usually implemented by `#[derive(ModbusRegisters)]` (feature
+/// `derive`)
+///
+/// The derive macro lays the fields out one after the other, each in
+/// `RegisterValue::REGISTERS` registers. Field attributes override
+/// the layout:
+///
+/// * `offset = 4` - Register of the field relative to the block
+/// * `ty = "i16"` - Type of the raw value, the field type by default
+/// * `word_order = "CDAB"` - Order of the raw value, also allowed on
+///   the struct
+/// * `scale = 0.1` - The field holds `raw * scale`, a finite nonzero
+///   number
+///
+/// Fields of primitive raw types whose registers overlap and a zero
+/// scale are compile errors.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use modbus_client::{ModbusRegisters, Request};
+///
+/// #[derive(Debug, PartialEq, ModbusRegisters)]
+/// #[modbus(word_order = "CDAB")]
+/// struct Drive {
+///     speed:       f32,
+///     #[modbus(ty = "i16", scale = 0.1)]
+///     temperature: f64,
+///     #[modbus(offset = 4)]
+///     status:      u16
+/// }
+///
+/// assert_eq!(Drive::REGISTERS, 5);
+/// let drive = Drive {
+///     speed:       1.5,
+///     temperature: -12.3,
+///     status:      0x0001
+/// };
+/// let registers = drive.to_registers();
+/// assert_eq!(registers, vec![0x0000, 0x3FC0, 0xFF85, 0x0000, 0x0001]);
+/// assert_eq!(Drive::from_registers(&registers), Some(drive));
+/// let request = Drive::read_request(0x01, 0x0100);
+/// # }
+/// ```
+pub trait ModbusRegisters: Sized {
+    /// Number of registers of the block
+    const REGISTERS: u16;
+
+    /// Decode the block from its registers, `None` if there are too
+    /// few
+    fn from_registers(registers: &[u16]) -> Option<Self>;
+
+    /// Encode the block into `REGISTERS` registers, unused registers
+    /// are 0
+    fn to_registers(&self) -> Vec<u16>;
+
+    /// Read holding registers request of the block at `address`
+    fn read_request(unit_id: u8, address: u16) -> Request {
+        Request::read_multiple_holding_registers_request(
+            unit_id,
+            address,
+            Self::REGISTERS
+        )
+    }
+
+    /// Write multiple holding registers request of the block at
+    /// `address`
+    fn write_request(&self, unit_id: u8, address: u16) -> Request {
+        Request::write_multiple_holding_registers_request(
+            unit_id,
+            address,
+            registers_to_bytes(&self.to_registers())
+        )
+    }
+
+    /// Decode the block from the response to its read request
+    fn from_response(response: &Response) -> Option<Self> {
+        Self::from_registers(&response.registers()?)
+    }
+}
+
+/// Decode consecutive values, `None` unless the registers hold a
+/// whole number of values
+pub fn decode<T: RegisterValue>(
+    registers: &[u16],
+    order: WordOrder
+) -> Option<Vec<T>> {
+    let chunks = registers.chunks_exact(T::REGISTERS);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    chunks
+        .map(|chunk| T::from_registers(chunk, order))
+        .collect()
+}
+
+/// Encode values into consecutive registers
+pub fn encode<T: RegisterValue>(
+    values: &[T],
+    order: WordOrder
+) -> Vec<u16> {
+    values
+        .iter()
+        .flat_map(|value| value.to_registers(order))
+        .collect()
+}
+
+/// Format of a string packed two characters per register
+///
+/// # Examples
+///
+/// ```
+/// use modbus_client::data::StringFormat;
+///
+/// let format = StringFormat::new(4);
+/// let registers = format.encode("SN-42").unwrap();
+/// assert_eq!(registers, vec![0x534E, 0x2D34, 0x3200, 0x0000]);
+/// assert_eq!(format.decode(&registers).as_deref(), Some("SN-42"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringFormat {
+    /// Fixed length in registers
+    pub registers: usize,
+    /// Low byte of a register holds the first character
+    pub byte_swap: bool,
+    /// Pads strings shorter than the fixed length, usually NUL or
+    /// space
+    pub padding:   u8,
+    /// Cut decoded strings at the first NUL and trim trailing
+    /// spaces and padding
+    pub trim:      bool
+}
+
+impl StringFormat {
+    /// NUL padded and trimmed string of `registers` registers
+    pub fn new(registers: usize) -> Self {
+        Self {
+            registers,
+            byte_swap: false,
+            padding: 0x00,
+            trim: true
+        }
+    }
+
+    /// Decode the first `registers` registers, `None` if there are
+    /// less. Invalid UTF-8 is replaced.
+    pub fn decode(&self, registers: &[u16]) -> Option<String> {
+        let registers = registers.get(..self.registers)?;
+        let mut bytes = registers_to_bytes(registers);
+        if self.byte_swap {
+            swap_bytes(&mut bytes);
+        }
+        let mut bytes = &bytes[..];
+        if self.trim {
+            if let Some(end) = bytes.iter().position(|b| *b == 0x00) {
+                bytes = &bytes[..end];
+            }
+            while let [rest @ .., last] = bytes {
+                if *last != b' ' && *last != self.padding {
+                    break;
+                }
+                bytes = rest;
+            }
+        }
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// Encode `value` into `registers` registers, `None` if it's too
+    /// long
+    pub fn encode(&self, value: &str) -> Option<Vec<u16>> {
+        let len = self.registers * 2;
+        if value.len() > len {
+            return None;
+        }
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(len, self.padding);
+        if self.byte_swap {
+            swap_bytes(&mut bytes);
+        }
+        Some(bytes_to_registers(&bytes))
+    }
+}
+
+/// Decode packed BCD, four digits per register, `None` for nibbles
+/// above 9 or values exceeding `u64`
+///
+/// # Examples
+///
+/// ```
+/// use modbus_client::data::{self, WordOrder};
+///
+/// let value = data::decode_bcd(&[0x0012, 0x3456], WordOrder::Abcd);
+/// assert_eq!(value, Some(123456));
+/// ```
+pub fn decode_bcd(
+    registers: &[u16],
+    order: WordOrder
+) -> Option<u64> {
+    let mut bytes = registers_to_bytes(registers);
+    order.apply(&mut bytes);
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0F])
+        .try_fold(0u64, |value, digit| {
+            if digit > 9 {
+                return None;
+            }
+            value.checked_mul(10)?.checked_add(digit as u64)
+        })
+}
+
+/// Encode `value` as packed BCD into `registers` registers, `None`
+/// if it has more than four digits per register
+pub fn encode_bcd(
+    value: u64,
+    registers: usize,
+    order: WordOrder
+) -> Option<Vec<u16>> {
+    let mut bytes = vec![0u8; registers * 2];
+    let mut rest = value;
+    for byte in bytes.iter_mut().rev() {
+        *byte = (rest % 10) as u8 | (((rest / 10) % 10) as u8) << 4;
+        rest /= 100;
+    }
+    if rest != 0 {
+        return None;
+    }
+    order.apply(&mut bytes);
+    Some(bytes_to_registers(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::*;
+    use crate::codec::TcpCodec;
+
+    const ORDERS: [(WordOrder, [u16; 2], [u16; 4]); 4] = [
+        (
+            WordOrder::Abcd,
+            [0x1122, 0x3344],
+            [0x1122, 0x3344, 0x5566, 0x7788]
+        ),
+        (
+            WordOrder::Cdab,
+            [0x3344, 0x1122],
+            [0x7788, 0x5566, 0x3344, 0x1122]
+        ),
+        (
+            WordOrder::Badc,
+            [0x2211, 0x4433],
+            [0x2211, 0x4433, 0x6655, 0x8877]
+        ),
+        (
+            WordOrder::Dcba,
+            [0x4433, 0x2211],
+            [0x8877, 0x6655, 0x4433, 0x2211]
+        )
+    ];
+
+    #[test]
+    fn word_orders_32() {
+        for (order, registers, _) in ORDERS {
+            assert_eq!(0x11223344u32.to_registers(order), registers);
+            assert_eq!(
+                u32::from_registers(&registers, order),
+                Some(0x11223344)
+            );
+            assert_eq!(
+                i32::from_registers(&registers, order),
+                Some(0x11223344)
+            );
+            let value = f32::from_bits(0x11223344);
+            assert_eq!(value.to_registers(order), registers);
+            assert_eq!(
+                f32::from_registers(&registers, order),
+                Some(value)
+            );
+        }
+    }
+
+    #[test]
+    fn word_orders_64() {
+        for (order, _, registers) in ORDERS {
+            let value = 0x1122334455667788u64;
+            assert_eq!(value.to_registers(order), registers);
+            assert_eq!(
+                u64::from_registers(&registers, order),
+                Some(value)
+            );
+            assert_eq!(
+                i64::from_registers(&registers, order),
+                Some(value as i64)
+            );
+            let value = f64::from_bits(value);
+            assert_eq!(value.to_registers(order), registers);
+            assert_eq!(
+                f64::from_registers(&registers, order),
+                Some(value)
+            );
+        }
+    }
+
+    #[test]
+    fn signed_values() {
+        assert_eq!((-2i16).to_registers(WordOrder::Dcba), [0xFEFF]);
+        assert_eq!(
+            (-2i32).to_registers(WordOrder::Cdab),
+            [0xFFFE, 0xFFFF]
+        );
+        assert_eq!(
+            i64::from_registers(&[0xFFFF; 4], WordOrder::Badc),
+            Some(-1)
+        );
+    }
+
+    #[test]
+    fn wrong_number_of_registers() {
+        assert_eq!(
+            u32::from_registers(&[0x1122], WordOrder::Abcd),
+            None
+        );
+        assert_eq!(
+            u64::from_registers(&[0x1122, 0x3344], WordOrder::Abcd),
+            None
+        );
+        // a remainder which doesn't hold a whole value
+        assert_eq!(
+            decode::<u32>(&[0x1122, 0x3344, 0x5566], WordOrder::Abcd),
+            None
+        );
+        assert_eq!(
+            decode::<u32>(
+                &[0x1122, 0x3344, 0x5566, 0x7788],
+                WordOrder::Cdab
+            ),
+            Some(vec![0x33441122, 0x77885566])
+        );
+        assert_eq!(decode::<u64>(&[], WordOrder::Abcd), Some(vec![]));
+    }
+
+    #[test]
+    fn write_values_request() {
+        let request = Request::write_values_request(
+            0x01,
+            0x0010,
+            &[0x11223344u32, 0x55667788],
+            WordOrder::Cdab
+        );
+        let mut dst = BytesMut::new();
+        TcpCodec::default().encode(request, &mut dst).unwrap();
+        assert_eq!(
+            &dst[6..],
+            [
+                0x01, 0x10, 0x00, 0x10, 0x00, 0x04, 0x08, 0x33, 0x44,
+                0x11, 0x22, 0x77, 0x88, 0x55, 0x66
+            ]
+        );
+    }
+
+    #[test]
+    fn response_values() {
+        let mut codec = TcpCodec::default();
+        let request =
+            Request::read_input_registers_request(0x01, 0, 3);
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        let mut src = BytesMut::from(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x09, 0x01, 0x04, 0x06,
+                0x44, 0x33, 0x22, 0x11, 0x00, 0x01
+            ][..]
+        );
+        let response = codec.decode(&mut src).unwrap().unwrap();
+        assert_eq!(
+            response.values::<u16>(WordOrder::Dcba),
+            Some(vec![0x3344, 0x1122, 0x0100])
+        );
+        // 3 registers don't hold whole 32 bit values
+        assert_eq!(response.values::<u32>(WordOrder::Dcba), None);
+    }
+
+    #[test]
+    fn string_round_trip() {
+        for padding in [0x00, b' ', 0xFF, b'*'] {
+            let format = StringFormat {
+                padding,
+                ..StringFormat::new(4)
+            };
+            let registers = format.encode("PUMP-1").unwrap();
+            assert_eq!(registers[3] & 0x00FF, padding as u16);
+            assert_eq!(
+                format.decode(&registers).as_deref(),
+                Some("PUMP-1")
+            );
+        }
+    }
+
+    #[test]
+    fn string_byte_swap() {
+        let format = StringFormat {
+            byte_swap: true,
+            padding: b' ',
+            ..StringFormat::new(2)
+        };
+        let registers = format.encode("ABC").unwrap();
+        assert_eq!(registers, vec![0x4241, 0x2043]);
+        assert_eq!(format.decode(&registers).as_deref(), Some("ABC"));
+    }
+
+    #[test]
+    fn string_untrimmed() {
+        let format = StringFormat {
+            padding: b'*',
+            trim: false,
+            ..StringFormat::new(2)
+        };
+        let registers = format.encode("AB").unwrap();
+        assert_eq!(
+            format.decode(&registers).as_deref(),
+            Some("AB**")
+        );
+    }
+
+    #[test]
+    fn bcd_invalid_nibble() {
+        assert_eq!(decode_bcd(&[0x001A], WordOrder::Abcd), None);
+        assert_eq!(decode_bcd(&[0xF000], WordOrder::Abcd), None);
+    }
+
+    #[test]
+    fn bcd_overflow() {
+        let max = [0x1844, 0x6744, 0x0737, 0x0955, 0x1615];
+        assert_eq!(decode_bcd(&max, WordOrder::Abcd), Some(u64::MAX));
+        assert_eq!(
+            encode_bcd(u64::MAX, 5, WordOrder::Abcd),
+            Some(max.to_vec())
+        );
+        // 20 digits above u64::MAX
+        assert_eq!(decode_bcd(&[0x9999; 5], WordOrder::Abcd), None);
+        // more than four digits per register
+        assert_eq!(
+            encode_bcd(9999, 1, WordOrder::Abcd),
+            Some(vec![0x9999])
+        );
+        assert_eq!(encode_bcd(12345, 1, WordOrder::Abcd), None);
+        assert_eq!(encode_bcd(1, 0, WordOrder::Abcd), None);
+    }
+
+    #[test]
+    fn bcd_word_orders() {
+        for (order, registers) in [
+            (WordOrder::Cdab, [0x5678, 0x1234]),
+            (WordOrder::Badc, [0x3412, 0x7856]),
+            (WordOrder::Dcba, [0x7856, 0x3412])
+        ] {
+            let encoded = encode_bcd(12345678, 2, order).unwrap();
+            assert_eq!(encoded, registers);
+            assert_eq!(decode_bcd(&encoded, order), Some(12345678));
+        }
+    }
+}
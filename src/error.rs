@@ -0,0 +1,185 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io
+};
+
+use crate::{Exception, ExceptionResponse};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors of the codecs and the `Client`
+#[derive(Debug)]
+pub enum Error {
+    /// CRC of a RTU frame doesn't match its content
+    CrcMismatch {
+        expected: u16,
+        actual:   u16
+    },
+    /// LRC of an ASCII frame doesn't match its content
+    LrcMismatch {
+        expected: u8,
+        actual:   u8
+    },
+    /// ASCII frame contains other characters than hex digits
+    InvalidAscii,
+    /// Function code of the response differs from the request
+    UnexpectedFunction {
+        expected: u8,
+        actual:   u8
+    },
+    /// Unit id of the response differs from the request
+    UnexpectedUnitId {
+        expected: u8,
+        actual:   u8
+    },
+    /// Protocol id of a MBAP header isn't modbus (0)
+    InvalidProtocolId(u16),
+    /// Length field of a MBAP header or data of an ASCII frame is
+    /// too short
+    InvalidLength(usize),
+    /// Quantity of a request is out of the range of its function
+    InvalidQuantity(u16),
+    /// Function code isn't a user defined one
+    InvalidFunction(u8),
+    /// Frame exceeds the maximum frame length of its framing
+    FrameTooLong(usize),
+    /// Register map can't be parsed
+    InvalidMap(String),
+    /// Point of a register map is invalid or can't be accessed
+    InvalidPoint {
+        name:   String,
+        reason: String
+    },
+    /// Register map has no point of this name
+    UnknownPoint(String),
+    /// Broadcast of a read request or over modbus TCP, neither is
+    /// answered
+    InvalidBroadcast,
+    /// Response doesn't fit the request
+    UnexpectedResponse,
+    /// Server answered with an exception response
+    Exception(Exception),
+    /// CANopen node aborted a SDO transfer with an abort code
+    SdoAbort(u32),
+    /// Server answered one of the requests of a split read or write
+    /// with an exception response, `address` is the first address
+    /// of that request
+    ChunkException {
+        address:   u16,
+        exception: Exception
+    },
+    /// No response within the timeout
+    Timeout,
+    /// Invalid TLS configuration or certificate
+    #[cfg(feature = "tls")]
+    Tls(tokio_rustls::rustls::Error),
+    Io(io::Error)
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CrcMismatch { expected, actual } => write!(
+                f,
+                "Invalid crc code: expected 0x{:0>4X}, actual \
+                 0x{:0>4X}",
+                expected, actual
+            ),
+            Error::LrcMismatch { expected, actual } => write!(
+                f,
+                "Invalid lrc code: expected 0x{:0>2X}, actual \
+                 0x{:0>2X}",
+                expected, actual
+            ),
+            Error::InvalidAscii => write!(f, "Invalid ascii frame"),
+            Error::UnexpectedFunction { expected, actual } => write!(
+                f,
+                "Invalid function: expected 0x{:0>2X}, actual \
+                 0x{:0>2X}",
+                expected, actual
+            ),
+            Error::UnexpectedUnitId { expected, actual } => write!(
+                f,
+                "Invalid unit id: expected 0x{:0>2X}, actual \
+                 0x{:0>2X}",
+                expected, actual
+            ),
+            Error::InvalidProtocolId(pid) => {
+                write!(f, "Invalid protocol id: 0x{:0>4X}", pid)
+            },
+            Error::InvalidLength(len) => {
+                write!(f, "Invalid length: {}", len)
+            },
+            Error::InvalidQuantity(quantity) => {
+                write!(f, "Invalid quantity: {}", quantity)
+            },
+            Error::InvalidFunction(code) => {
+                write!(f, "Invalid function code: 0x{:0>2X}", code)
+            },
+            Error::FrameTooLong(len) => {
+                write!(f, "Frame too long: {} bytes", len)
+            },
+            Error::InvalidMap(e) => {
+                write!(f, "Invalid register map: {}", e)
+            },
+            Error::InvalidPoint { name, reason } => {
+                write!(f, "Invalid point {}: {}", name, reason)
+            },
+            Error::UnknownPoint(name) => {
+                write!(f, "Unknown point: {}", name)
+            },
+            Error::InvalidBroadcast => {
+                write!(f, "Invalid broadcast request")
+            },
+            Error::UnexpectedResponse => {
+                write!(f, "Unexpected response")
+            },
+            Error::Exception(exception) => {
+                write!(f, "Exception response: {}", exception)
+            },
+            Error::SdoAbort(code) => {
+                write!(f, "Sdo transfer aborted: 0x{:0>8X}", code)
+            },
+            Error::ChunkException { address, exception } => write!(
+                f,
+                "Exception response at address 0x{:0>4X}: {}",
+                address, exception
+            ),
+            Error::Timeout => write!(f, "Response timeout"),
+            #[cfg(feature = "tls")]
+            Error::Tls(e) => write!(f, "Tls error: {}", e),
+            Error::Io(e) => write!(f, "Io error: {}", e)
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            #[cfg(feature = "tls")]
+            Error::Tls(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    /// TLS errors, e.g. a rejected certificate, are wrapped into io
+    /// errors by the handshake and unwrapped again
+    fn from(e: io::Error) -> Self {
+        #[cfg(feature = "tls")]
+        if let Some(tls) = e.get_ref().and_then(|inner| {
+            inner.downcast_ref::<tokio_rustls::rustls::Error>()
+        }) {
+            return Error::Tls(tls.clone());
+        }
+        Error::Io(e)
+    }
+}
+
+impl From<ExceptionResponse> for Error {
+    fn from(rs: ExceptionResponse) -> Self {
+        Error::Exception(rs.exception)
+    }
+}
@@ -0,0 +1,97 @@
+use std::fmt::{self, Display, Formatter};
+
+use bytes::Bytes;
+
+/// Exception code of a modbus exception response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exception {
+    IllegalFunction,
+    IllegalDataAddress,
+    IllegalDataValue,
+    ServerDeviceFailure,
+    Acknowledge,
+    ServerDeviceBusy,
+    MemoryParityError,
+    GatewayPathUnavailable,
+    GatewayTargetDeviceFailedToRespond,
+    Unknown(u8)
+}
+
+impl Exception {
+    pub fn to_code(&self) -> u8 {
+        match self {
+            Exception::IllegalFunction => 0x01,
+            Exception::IllegalDataAddress => 0x02,
+            Exception::IllegalDataValue => 0x03,
+            Exception::ServerDeviceFailure => 0x04,
+            Exception::Acknowledge => 0x05,
+            Exception::ServerDeviceBusy => 0x06,
+            Exception::MemoryParityError => 0x08,
+            Exception::GatewayPathUnavailable => 0x0A,
+            Exception::GatewayTargetDeviceFailedToRespond => 0x0B,
+            Exception::Unknown(code) => *code
+        }
+    }
+}
+
+impl From<u8> for Exception {
+    fn from(code: u8) -> Self {
+        match code {
+            0x01 => Exception::IllegalFunction,
+            0x02 => Exception::IllegalDataAddress,
+            0x03 => Exception::IllegalDataValue,
+            0x04 => Exception::ServerDeviceFailure,
+            0x05 => Exception::Acknowledge,
+            0x06 => Exception::ServerDeviceBusy,
+            0x08 => Exception::MemoryParityError,
+            0x0A => Exception::GatewayPathUnavailable,
+            0x0B => Exception::GatewayTargetDeviceFailedToRespond,
+            code => Exception::Unknown(code)
+        }
+    }
+}
+
+impl Display for Exception {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let desc = match self {
+            Exception::IllegalFunction => "Illegal function",
+            Exception::IllegalDataAddress => "Illegal data address",
+            Exception::IllegalDataValue => "Illegal data value",
+            Exception::ServerDeviceFailure => "Server device failure",
+            Exception::Acknowledge => "Acknowledge",
+            Exception::ServerDeviceBusy => "Server device busy",
+            Exception::MemoryParityError => "Memory parity error",
+            Exception::GatewayPathUnavailable => {
+                "Gateway path unavailable"
+            },
+            Exception::GatewayTargetDeviceFailedToRespond => {
+                "Gateway target device failed to respond"
+            },
+            Exception::Unknown(_) => "Unknown exception"
+        };
+        write!(f, "{} (0x{:0>2X})", desc, self.to_code())
+    }
+}
+
+/// Body of an exception response: the exception code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExceptionResponse {
+    pub exception: Exception
+}
+
+impl From<Bytes> for ExceptionResponse {
+    fn from(bytes: Bytes) -> Self {
+        let code = bytes.first().copied().unwrap_or_default();
+        Self {
+            exception: Exception::from(code)
+        }
+    }
+}
+
+impl Display for ExceptionResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Exception response: {}", self.exception)
+    }
+}
+
+impl std::error::Error for ExceptionResponse {}
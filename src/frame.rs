@@ -0,0 +1,766 @@
+//! Request and response bodies of the function codes not covered by
+//! `easy_modbus`
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+use crate::util::bytes_to_registers;
+
+/// Mask write register request body (Function Code: 0x16)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskWriteRegisterRequest {
+    pub address:  u16,
+    pub and_mask: u16,
+    pub or_mask:  u16
+}
+
+impl MaskWriteRegisterRequest {
+    pub fn new(address: u16, and_mask: u16, or_mask: u16) -> Self {
+        Self {
+            address,
+            and_mask,
+            or_mask
+        }
+    }
+}
+
+impl From<MaskWriteRegisterRequest> for BytesMut {
+    fn from(request: MaskWriteRegisterRequest) -> Self {
+        let mut buf = BytesMut::with_capacity(6);
+        buf.put_u16(request.address);
+        buf.put_u16(request.and_mask);
+        buf.put_u16(request.or_mask);
+        buf
+    }
+}
+
+/// Mask write register response body, an echo of the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaskWriteRegisterResponse {
+    pub address:  u16,
+    pub and_mask: u16,
+    pub or_mask:  u16
+}
+
+impl From<Bytes> for MaskWriteRegisterResponse {
+    fn from(mut src: Bytes) -> Self {
+        Self {
+            address:  src.get_u16(),
+            and_mask: src.get_u16(),
+            or_mask:  src.get_u16()
+        }
+    }
+}
+
+/// Read/write multiple registers request body (Function Code: 0x17)
+///
+/// The server writes the registers before reading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadWriteMultipleRegistersRequest {
+    pub read_address:   u16,
+    pub read_quantity:  u16,
+    pub write_address:  u16,
+    pub write_quantity: u16,
+    pub bytes_number:   u8,
+    pub values:         Vec<u8>
+}
+
+impl ReadWriteMultipleRegistersRequest {
+    /// Max number of registers to read
+    pub const MAX_READ_QUANTITY: u16 = 125;
+    /// Max number of registers to write
+    pub const MAX_WRITE_QUANTITY: u16 = 121;
+
+    pub fn new(
+        read_address: u16,
+        read_quantity: u16,
+        write_address: u16,
+        values: Vec<u8>
+    ) -> Self {
+        Self {
+            read_address,
+            read_quantity,
+            write_address,
+            write_quantity: values.len() as u16 / 2,
+            bytes_number: values.len() as u8,
+            values
+        }
+    }
+}
+
+impl From<ReadWriteMultipleRegistersRequest> for BytesMut {
+    fn from(request: ReadWriteMultipleRegistersRequest) -> Self {
+        let mut buf =
+            BytesMut::with_capacity(9 + request.values.len());
+        buf.put_u16(request.read_address);
+        buf.put_u16(request.read_quantity);
+        buf.put_u16(request.write_address);
+        buf.put_u16(request.write_quantity);
+        buf.put_u8(request.bytes_number);
+        buf.put_slice(&request.values);
+        buf
+    }
+}
+
+/// Read/write multiple registers response body, the registers read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadWriteMultipleRegistersResponse {
+    pub bytes_number: u8,
+    pub values:       Vec<u8>
+}
+
+impl ReadWriteMultipleRegistersResponse {
+    pub fn get_values(&self) -> Vec<u8> {
+        self.values.clone()
+    }
+}
+
+impl From<Bytes> for ReadWriteMultipleRegistersResponse {
+    fn from(mut src: Bytes) -> Self {
+        let bytes_number = src.get_u8();
+        Self {
+            bytes_number,
+            values: src.to_vec()
+        }
+    }
+}
+
+/// New value of a register after a mask write, as computed by the
+/// server: `(value & and_mask) | (or_mask & !and_mask)`
+///
+/// # Examples
+///
+/// ```
+/// use modbus_client::apply_mask;
+/// assert_eq!(apply_mask(0x0012, 0x00F2, 0x0025), 0x0017);
+/// ```
+pub fn apply_mask(value: u16, and_mask: u16, or_mask: u16) -> u16 {
+    (value & and_mask) | (or_mask & !and_mask)
+}
+
+/// MEI type of Read Device Identification
+pub const MEI_READ_DEVICE_IDENTIFICATION: u8 = 0x0E;
+
+/// Access type of a Read Device Identification request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadDeviceIdCode {
+    /// Stream of the basic objects: vendor name, product code and
+    /// revision
+    Basic,
+    /// Stream of the basic and regular objects
+    Regular,
+    /// Stream of the basic, regular and extended objects
+    Extended,
+    /// One specific object
+    Individual
+}
+
+impl ReadDeviceIdCode {
+    pub fn to_code(&self) -> u8 {
+        match self {
+            ReadDeviceIdCode::Basic => 0x01,
+            ReadDeviceIdCode::Regular => 0x02,
+            ReadDeviceIdCode::Extended => 0x03,
+            ReadDeviceIdCode::Individual => 0x04
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<ReadDeviceIdCode> {
+        let code = match code {
+            0x01 => ReadDeviceIdCode::Basic,
+            0x02 => ReadDeviceIdCode::Regular,
+            0x03 => ReadDeviceIdCode::Extended,
+            0x04 => ReadDeviceIdCode::Individual,
+            _ => return None
+        };
+        Some(code)
+    }
+}
+
+/// Read device identification request body (Function Code: 0x2B,
+/// MEI type: 0x0E)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadDeviceIdentificationRequest {
+    pub read_device_id_code: ReadDeviceIdCode,
+    /// Object to start the stream with, or the object to read for
+    /// individual access
+    pub object_id:           u8
+}
+
+impl ReadDeviceIdentificationRequest {
+    pub fn new(
+        read_device_id_code: ReadDeviceIdCode,
+        object_id: u8
+    ) -> Self {
+        Self {
+            read_device_id_code,
+            object_id
+        }
+    }
+}
+
+impl From<ReadDeviceIdentificationRequest> for BytesMut {
+    fn from(request: ReadDeviceIdentificationRequest) -> Self {
+        let mut buf = BytesMut::with_capacity(3);
+        buf.put_u8(MEI_READ_DEVICE_IDENTIFICATION);
+        buf.put_u8(request.read_device_id_code.to_code());
+        buf.put_u8(request.object_id);
+        buf
+    }
+}
+
+/// Read device identification response body, one page of objects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadDeviceIdentificationResponse {
+    pub mei_type:            u8,
+    pub read_device_id_code: u8,
+    pub conformity_level:    u8,
+    /// Further objects don't fit into this response, request them
+    /// starting with `next_object_id`
+    pub more_follows:        bool,
+    pub next_object_id:      u8,
+    /// Object ids and values
+    pub objects:             Vec<(u8, Vec<u8>)>
+}
+
+impl From<Bytes> for ReadDeviceIdentificationResponse {
+    fn from(mut src: Bytes) -> Self {
+        let mei_type = src.get_u8();
+        let read_device_id_code = src.get_u8();
+        let conformity_level = src.get_u8();
+        let more_follows = src.get_u8() == 0xFF;
+        let next_object_id = src.get_u8();
+        let objects_number = src.get_u8();
+        let objects = (0..objects_number)
+            .map(|_| {
+                let id = src.get_u8();
+                let len = src.get_u8() as usize;
+                (id, src.split_to(len).to_vec())
+            })
+            .collect();
+        Self {
+            mei_type,
+            read_device_id_code,
+            conformity_level,
+            more_follows,
+            next_object_id,
+            objects
+        }
+    }
+}
+
+/// Read exception status request body (Function Code: 0x07), empty
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadExceptionStatusRequest;
+
+impl From<ReadExceptionStatusRequest> for BytesMut {
+    fn from(_: ReadExceptionStatusRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+/// Read exception status response body, the eight exception status
+/// outputs of the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadExceptionStatusResponse {
+    pub status: u8
+}
+
+impl From<Bytes> for ReadExceptionStatusResponse {
+    fn from(mut src: Bytes) -> Self {
+        Self {
+            status: src.get_u8()
+        }
+    }
+}
+
+/// Sub-function of a diagnostics request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsSubFunction {
+    /// Echo the request data
+    ReturnQueryData,
+    /// Restart the serial line port, data 0xFF00 also clears the
+    /// communications event log
+    RestartCommunicationsOption,
+    ReturnDiagnosticRegister,
+    ChangeAsciiInputDelimiter,
+    /// Stop answering requests until restarted, not answered
+    ForceListenOnlyMode,
+    ClearCountersAndDiagnosticRegister,
+    ReturnBusMessageCount,
+    ReturnBusCommunicationErrorCount,
+    ReturnBusExceptionErrorCount,
+    ReturnServerMessageCount,
+    ReturnServerNoResponseCount,
+    ReturnServerNakCount,
+    ReturnServerBusyCount,
+    ReturnBusCharacterOverrunCount,
+    ClearOverrunCounterAndFlag,
+    Unknown(u16)
+}
+
+impl DiagnosticsSubFunction {
+    pub fn to_code(&self) -> u16 {
+        match self {
+            DiagnosticsSubFunction::ReturnQueryData => 0x00,
+            DiagnosticsSubFunction::RestartCommunicationsOption => 0x01,
+            DiagnosticsSubFunction::ReturnDiagnosticRegister => 0x02,
+            DiagnosticsSubFunction::ChangeAsciiInputDelimiter => 0x03,
+            DiagnosticsSubFunction::ForceListenOnlyMode => 0x04,
+            DiagnosticsSubFunction::ClearCountersAndDiagnosticRegister => {
+                0x0A
+            },
+            DiagnosticsSubFunction::ReturnBusMessageCount => 0x0B,
+            DiagnosticsSubFunction::ReturnBusCommunicationErrorCount => {
+                0x0C
+            },
+            DiagnosticsSubFunction::ReturnBusExceptionErrorCount => 0x0D,
+            DiagnosticsSubFunction::ReturnServerMessageCount => 0x0E,
+            DiagnosticsSubFunction::ReturnServerNoResponseCount => 0x0F,
+            DiagnosticsSubFunction::ReturnServerNakCount => 0x10,
+            DiagnosticsSubFunction::ReturnServerBusyCount => 0x11,
+            DiagnosticsSubFunction::ReturnBusCharacterOverrunCount => {
+                0x12
+            },
+            DiagnosticsSubFunction::ClearOverrunCounterAndFlag => 0x14,
+            DiagnosticsSubFunction::Unknown(code) => *code
+        }
+    }
+}
+
+impl From<u16> for DiagnosticsSubFunction {
+    fn from(code: u16) -> Self {
+        match code {
+            0x00 => DiagnosticsSubFunction::ReturnQueryData,
+            0x01 => DiagnosticsSubFunction::RestartCommunicationsOption,
+            0x02 => DiagnosticsSubFunction::ReturnDiagnosticRegister,
+            0x03 => DiagnosticsSubFunction::ChangeAsciiInputDelimiter,
+            0x04 => DiagnosticsSubFunction::ForceListenOnlyMode,
+            0x0A => {
+                DiagnosticsSubFunction::ClearCountersAndDiagnosticRegister
+            },
+            0x0B => DiagnosticsSubFunction::ReturnBusMessageCount,
+            0x0C => {
+                DiagnosticsSubFunction::ReturnBusCommunicationErrorCount
+            },
+            0x0D => DiagnosticsSubFunction::ReturnBusExceptionErrorCount,
+            0x0E => DiagnosticsSubFunction::ReturnServerMessageCount,
+            0x0F => DiagnosticsSubFunction::ReturnServerNoResponseCount,
+            0x10 => DiagnosticsSubFunction::ReturnServerNakCount,
+            0x11 => DiagnosticsSubFunction::ReturnServerBusyCount,
+            0x12 => DiagnosticsSubFunction::ReturnBusCharacterOverrunCount,
+            0x14 => DiagnosticsSubFunction::ClearOverrunCounterAndFlag,
+            code => DiagnosticsSubFunction::Unknown(code)
+        }
+    }
+}
+
+/// Diagnostics request body (Function Code: 0x08)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticsRequest {
+    pub sub_function: DiagnosticsSubFunction,
+    /// Data field, two bytes for all sub-functions but Return Query
+    /// Data
+    pub data:         Vec<u8>
+}
+
+impl DiagnosticsRequest {
+    pub fn new(
+        sub_function: DiagnosticsSubFunction,
+        data: Vec<u8>
+    ) -> Self {
+        Self { sub_function, data }
+    }
+}
+
+impl From<DiagnosticsRequest> for BytesMut {
+    fn from(request: DiagnosticsRequest) -> Self {
+        let mut buf = BytesMut::with_capacity(2 + request.data.len());
+        buf.put_u16(request.sub_function.to_code());
+        buf.put_slice(&request.data);
+        buf
+    }
+}
+
+/// Diagnostics response body, as long as the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticsResponse {
+    pub sub_function: DiagnosticsSubFunction,
+    pub data:         Vec<u8>
+}
+
+impl From<Bytes> for DiagnosticsResponse {
+    fn from(mut src: Bytes) -> Self {
+        Self {
+            sub_function: src.get_u16().into(),
+            data:         src.to_vec()
+        }
+    }
+}
+
+/// Get comm event counter request body (Function Code: 0x0B), empty
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GetCommEventCounterRequest;
+
+impl From<GetCommEventCounterRequest> for BytesMut {
+    fn from(_: GetCommEventCounterRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+/// Get comm event counter response body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetCommEventCounterResponse {
+    /// 0xFFFF while the device is busy with a previous command
+    pub status:      u16,
+    /// Number of successfully completed messages
+    pub event_count: u16
+}
+
+impl From<Bytes> for GetCommEventCounterResponse {
+    fn from(mut src: Bytes) -> Self {
+        Self {
+            status:      src.get_u16(),
+            event_count: src.get_u16()
+        }
+    }
+}
+
+/// Get comm event log request body (Function Code: 0x0C), empty
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GetCommEventLogRequest;
+
+impl From<GetCommEventLogRequest> for BytesMut {
+    fn from(_: GetCommEventLogRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+/// Get comm event log response body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GetCommEventLogResponse {
+    pub bytes_number:  u8,
+    /// 0xFFFF while the device is busy with a previous command
+    pub status:        u16,
+    pub event_count:   u16,
+    pub message_count: u16,
+    /// Up to 64 events, the most recent first
+    pub events:        Vec<u8>
+}
+
+impl From<Bytes> for GetCommEventLogResponse {
+    fn from(mut src: Bytes) -> Self {
+        Self {
+            bytes_number:  src.get_u8(),
+            status:        src.get_u16(),
+            event_count:   src.get_u16(),
+            message_count: src.get_u16(),
+            events:        src.to_vec()
+        }
+    }
+}
+
+/// Report server id request body (Function Code: 0x11), empty
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReportServerIdRequest;
+
+impl From<ReportServerIdRequest> for BytesMut {
+    fn from(_: ReportServerIdRequest) -> Self {
+        BytesMut::new()
+    }
+}
+
+/// Report server id response body: the device specific server id,
+/// the run indicator status (0x00 off, 0xFF on) and additional data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportServerIdResponse {
+    pub bytes_number: u8,
+    pub values:       Vec<u8>
+}
+
+impl ReportServerIdResponse {
+    pub fn get_values(&self) -> Vec<u8> {
+        self.values.clone()
+    }
+}
+
+impl From<Bytes> for ReportServerIdResponse {
+    fn from(mut src: Bytes) -> Self {
+        let bytes_number = src.get_u8();
+        Self {
+            bytes_number,
+            values: src.to_vec()
+        }
+    }
+}
+
+/// Reference type of file record sub-requests
+pub const FILE_REFERENCE_TYPE: u8 = 0x06;
+
+/// Max value of the byte count of file record requests and
+/// responses, which keeps their PDUs within 253 bytes
+pub const FILE_RECORD_MAX_BYTES: usize = 0xF5;
+
+/// Last record number of a file
+pub const FILE_MAX_RECORD_NUMBER: u16 = 0x270F;
+
+/// Whether `length` records starting at `record_number` are within
+/// the record numbers of a file
+pub(crate) fn records_in_file(
+    record_number: u16,
+    length: usize
+) -> bool {
+    record_number <= FILE_MAX_RECORD_NUMBER
+        && record_number as usize + length
+            <= FILE_MAX_RECORD_NUMBER as usize + 1
+}
+
+/// Sub-request of a read file record request: `record_length`
+/// registers starting at `record_number` of file `file_number`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileSubRequest {
+    pub file_number:   u16,
+    /// 0x0000 to 0x270F
+    pub record_number: u16,
+    pub record_length: u16
+}
+
+impl FileSubRequest {
+    pub fn new(
+        file_number: u16,
+        record_number: u16,
+        record_length: u16
+    ) -> Self {
+        Self {
+            file_number,
+            record_number,
+            record_length
+        }
+    }
+}
+
+/// Registers of a file starting at `record_number`, as written by a
+/// write file record request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRecord {
+    pub file_number:   u16,
+    /// 0x0000 to 0x270F
+    pub record_number: u16,
+    pub values:        Vec<u16>
+}
+
+impl FileRecord {
+    pub fn new(
+        file_number: u16,
+        record_number: u16,
+        values: Vec<u16>
+    ) -> Self {
+        Self {
+            file_number,
+            record_number,
+            values
+        }
+    }
+}
+
+/// Read file record request body (Function Code: 0x14)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadFileRecordRequest {
+    pub sub_requests: Vec<FileSubRequest>
+}
+
+impl ReadFileRecordRequest {
+    /// Max record length of a request with a single sub-request
+    pub const MAX_RECORD_LENGTH: u16 =
+        ((FILE_RECORD_MAX_BYTES - 2) / 2) as u16;
+    /// Length of a sub-request
+    pub const SUB_REQUEST_LEN: usize = 7;
+
+    pub fn new(sub_requests: Vec<FileSubRequest>) -> Self {
+        Self { sub_requests }
+    }
+
+    /// Value of the byte count of the request
+    pub fn bytes_number(&self) -> usize {
+        self.sub_requests.len() * Self::SUB_REQUEST_LEN
+    }
+
+    /// Value of the byte count of the response
+    pub fn response_bytes_number(&self) -> usize {
+        self.sub_requests
+            .iter()
+            .map(|sub| 2 + 2 * sub.record_length as usize)
+            .sum()
+    }
+}
+
+impl From<ReadFileRecordRequest> for BytesMut {
+    fn from(request: ReadFileRecordRequest) -> Self {
+        let mut buf =
+            BytesMut::with_capacity(1 + request.bytes_number());
+        buf.put_u8(request.bytes_number() as u8);
+        for sub in request.sub_requests {
+            buf.put_u8(FILE_REFERENCE_TYPE);
+            buf.put_u16(sub.file_number);
+            buf.put_u16(sub.record_number);
+            buf.put_u16(sub.record_length);
+        }
+        buf
+    }
+}
+
+/// Read file record response body, the registers of each
+/// sub-request in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadFileRecordResponse {
+    pub bytes_number: u8,
+    pub records:      Vec<Vec<u16>>
+}
+
+impl From<Bytes> for ReadFileRecordResponse {
+    fn from(mut src: Bytes) -> Self {
+        let bytes_number = src.get_u8();
+        let mut records = Vec::new();
+        while src.has_remaining() {
+            // the file response length counts the reference type
+            let len = (src.get_u8() as usize).min(src.remaining());
+            let mut record = src.split_to(len);
+            if record.has_remaining() {
+                record.advance(1);
+            }
+            records.push(bytes_to_registers(&record));
+        }
+        Self {
+            bytes_number,
+            records
+        }
+    }
+}
+
+/// Write file record request body (Function Code: 0x15)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteFileRecordRequest {
+    pub records: Vec<FileRecord>
+}
+
+impl WriteFileRecordRequest {
+    /// Max record length of a request with a single record
+    pub const MAX_RECORD_LENGTH: u16 =
+        ((FILE_RECORD_MAX_BYTES - 7) / 2) as u16;
+
+    pub fn new(records: Vec<FileRecord>) -> Self {
+        Self { records }
+    }
+
+    /// Value of the byte count of the request and the response
+    pub fn bytes_number(&self) -> usize {
+        self.records
+            .iter()
+            .map(|record| 7 + 2 * record.values.len())
+            .sum()
+    }
+}
+
+impl From<WriteFileRecordRequest> for BytesMut {
+    fn from(request: WriteFileRecordRequest) -> Self {
+        let mut buf =
+            BytesMut::with_capacity(1 + request.bytes_number());
+        buf.put_u8(request.bytes_number() as u8);
+        for record in request.records {
+            buf.put_u8(FILE_REFERENCE_TYPE);
+            buf.put_u16(record.file_number);
+            buf.put_u16(record.record_number);
+            buf.put_u16(record.values.len() as u16);
+            for value in record.values {
+                buf.put_u16(value);
+            }
+        }
+        buf
+    }
+}
+
+/// Write file record response body, an echo of the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteFileRecordResponse {
+    pub bytes_number: u8,
+    pub records:      Vec<FileRecord>
+}
+
+impl From<Bytes> for WriteFileRecordResponse {
+    fn from(mut src: Bytes) -> Self {
+        let bytes_number = src.get_u8();
+        let mut records = Vec::new();
+        while src.len() >= 7 {
+            // reference type
+            src.advance(1);
+            let file_number = src.get_u16();
+            let record_number = src.get_u16();
+            let record_length =
+                (src.get_u16() as usize).min(src.len() / 2);
+            let values =
+                (0..record_length).map(|_| src.get_u16()).collect();
+            records.push(FileRecord::new(
+                file_number,
+                record_number,
+                values
+            ));
+        }
+        Self {
+            bytes_number,
+            records
+        }
+    }
+}
+
+/// Read FIFO queue request body (Function Code: 0x18)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadFifoQueueRequest {
+    /// Address of the FIFO pointer register
+    pub fifo_address: u16
+}
+
+impl ReadFifoQueueRequest {
+    /// Max number of registers in a queue, larger queues are
+    /// answered with an illegal data value exception
+    pub const MAX_FIFO_COUNT: u16 = 31;
+
+    pub fn new(fifo_address: u16) -> Self {
+        Self { fifo_address }
+    }
+}
+
+impl From<ReadFifoQueueRequest> for BytesMut {
+    fn from(request: ReadFifoQueueRequest) -> Self {
+        let mut buf = BytesMut::with_capacity(2);
+        buf.put_u16(request.fifo_address);
+        buf
+    }
+}
+
+/// Read FIFO queue response body
+///
+/// Unlike other read responses its byte count has two bytes, it
+/// counts the FIFO count and the values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadFifoQueueResponse {
+    pub bytes_number: u16,
+    pub fifo_count:   u16,
+    pub values:       Vec<u8>
+}
+
+impl ReadFifoQueueResponse {
+    pub fn get_values(&self) -> Vec<u8> {
+        self.values.clone()
+    }
+}
+
+impl From<Bytes> for ReadFifoQueueResponse {
+    fn from(mut src: Bytes) -> Self {
+        let bytes_number = src.get_u16();
+        let fifo_count = src.get_u16();
+        Self {
+            bytes_number,
+            fifo_count,
+            values: src.to_vec()
+        }
+    }
+}
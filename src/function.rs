@@ -0,0 +1,90 @@
+/// Function code of a modbus request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    ReadCoils,
+    ReadDiscreteInputs,
+    ReadMultipleHoldingRegisters,
+    ReadInputRegisters,
+    WriteSingleCoil,
+    WriteSingleHoldingRegister,
+    ReadExceptionStatus,
+    Diagnostics,
+    GetCommEventCounter,
+    GetCommEventLog,
+    WriteMultipleCoils,
+    WriteMultipleHoldingRegisters,
+    ReportServerId,
+    ReadFileRecord,
+    WriteFileRecord,
+    MaskWriteRegister,
+    ReadWriteMultipleRegisters,
+    ReadFifoQueue,
+    /// Modbus encapsulated interface (MEI) transport, e.g. Read
+    /// Device Identification
+    EncapsulatedInterfaceTransport,
+    /// User defined function code, see `CustomFunction`
+    Custom(u8)
+}
+
+impl Function {
+    pub fn to_code(&self) -> u8 {
+        match self {
+            Function::ReadCoils => 0x01,
+            Function::ReadDiscreteInputs => 0x02,
+            Function::ReadMultipleHoldingRegisters => 0x03,
+            Function::ReadInputRegisters => 0x04,
+            Function::WriteSingleCoil => 0x05,
+            Function::WriteSingleHoldingRegister => 0x06,
+            Function::ReadExceptionStatus => 0x07,
+            Function::Diagnostics => 0x08,
+            Function::GetCommEventCounter => 0x0B,
+            Function::GetCommEventLog => 0x0C,
+            Function::WriteMultipleCoils => 0x0F,
+            Function::WriteMultipleHoldingRegisters => 0x10,
+            Function::ReportServerId => 0x11,
+            Function::ReadFileRecord => 0x14,
+            Function::WriteFileRecord => 0x15,
+            Function::MaskWriteRegister => 0x16,
+            Function::ReadWriteMultipleRegisters => 0x17,
+            Function::ReadFifoQueue => 0x18,
+            Function::EncapsulatedInterfaceTransport => 0x2B,
+            Function::Custom(code) => *code
+        }
+    }
+
+    /// Whether `code` is one of the user defined function codes, 65
+    /// to 72 and 100 to 110
+    pub fn is_user_defined(code: u8) -> bool {
+        matches!(code, 65..=72 | 100..=110)
+    }
+
+    /// Function of a code without the exception bit, codes without a
+    /// public function are `Custom`. `None` if the exception bit is
+    /// set.
+    pub fn from_code(code: u8) -> Option<Function> {
+        let function = match code {
+            0x01 => Function::ReadCoils,
+            0x02 => Function::ReadDiscreteInputs,
+            0x03 => Function::ReadMultipleHoldingRegisters,
+            0x04 => Function::ReadInputRegisters,
+            0x05 => Function::WriteSingleCoil,
+            0x06 => Function::WriteSingleHoldingRegister,
+            0x07 => Function::ReadExceptionStatus,
+            0x08 => Function::Diagnostics,
+            0x0B => Function::GetCommEventCounter,
+            0x0C => Function::GetCommEventLog,
+            0x0F => Function::WriteMultipleCoils,
+            0x10 => Function::WriteMultipleHoldingRegisters,
+            0x11 => Function::ReportServerId,
+            0x14 => Function::ReadFileRecord,
+            0x15 => Function::WriteFileRecord,
+            0x16 => Function::MaskWriteRegister,
+            0x17 => Function::ReadWriteMultipleRegisters,
+            0x18 => Function::ReadFifoQueue,
+            0x2B => Function::EncapsulatedInterfaceTransport,
+            0x80.. => return None,
+            code => Function::Custom(code)
+        };
+        Some(function)
+    }
+}
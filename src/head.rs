@@ -0,0 +1,79 @@
+use bytes::{BufMut, BytesMut};
+
+use crate::Function;
+
+/// Framing of a modbus message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// MBAP header + PDU, used over TCP/IP networks
+    Tcp,
+    /// Unit id + PDU + CRC, used in serial communication
+    Rtu,
+    /// ':' + hex encoded unit id + PDU + LRC + CRLF, used by legacy
+    /// serial devices
+    Ascii
+}
+
+/// Modbus message head
+///
+/// For TCP this is the MBAP header (transaction id, protocol id,
+/// length and unit id) followed by the function code, for RTU and
+/// ASCII only the unit id and the function code.
+#[derive(Debug, Clone)]
+pub struct Head {
+    /// Transaction id, always 0 for RTU and ASCII
+    pub tid:          u16,
+    /// Protocol id, always 0 for modbus
+    pub pid:          u16,
+    /// Unit id (server address)
+    pub uid:          u8,
+    pub function:     Function,
+    pub version:      Version,
+    pub is_exception: bool
+}
+
+impl Head {
+    /// Unit id addressing all servers of a serial line
+    pub const BROADCAST_UID: u8 = 0;
+    /// Length of the MBAP header, unit id included
+    pub const MBAP_LEN: usize = 7;
+    /// Protocol id of modbus in the MBAP header
+    pub const PROTOCOL_ID: u16 = 0;
+
+    pub fn new(
+        tid: u16,
+        uid: u8,
+        function: Function,
+        version: Version,
+        is_exception: bool
+    ) -> Self {
+        Self {
+            tid,
+            pid: Self::PROTOCOL_ID,
+            uid,
+            function,
+            version,
+            is_exception
+        }
+    }
+
+    /// Write the head of a message whose body (the PDU without the
+    /// function code) is `body_length` bytes long.
+    ///
+    /// The MBAP length field counts the unit id, the function code
+    /// and the body.
+    pub fn to_bytes(&self, body_length: usize, dst: &mut BytesMut) {
+        if self.version == Version::Tcp {
+            dst.put_u16(self.tid);
+            dst.put_u16(self.pid);
+            dst.put_u16(body_length as u16 + 2);
+        }
+        dst.put_u8(self.uid);
+        let code = self.function.to_code();
+        dst.put_u8(if self.is_exception {
+            code | 0x80
+        } else {
+            code
+        });
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::BTreeMap;
+
+/// Identification of a device, collected from the objects of Read
+/// Device Identification responses
+///
+/// Objects the device doesn't provide, or which weren't requested,
+/// are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceIdentification {
+    /// Conformity level of the device, i.e. the access types and
+    /// object categories it supports
+    pub conformity_level:      u8,
+    /// Object 0x00
+    pub vendor_name:           Option<String>,
+    /// Object 0x01
+    pub product_code:          Option<String>,
+    /// Object 0x02
+    pub major_minor_revision:  Option<String>,
+    /// Object 0x03
+    pub vendor_url:            Option<String>,
+    /// Object 0x04
+    pub product_name:          Option<String>,
+    /// Object 0x05
+    pub model_name:            Option<String>,
+    /// Object 0x06
+    pub user_application_name: Option<String>,
+    /// Reserved and device dependent objects, by id
+    pub extended:              BTreeMap<u8, Vec<u8>>
+}
+
+impl DeviceIdentification {
+    /// Store the value of an object
+    pub fn insert(&mut self, id: u8, value: Vec<u8>) {
+        let field = match id {
+            0x00 => &mut self.vendor_name,
+            0x01 => &mut self.product_code,
+            0x02 => &mut self.major_minor_revision,
+            0x03 => &mut self.vendor_url,
+            0x04 => &mut self.product_name,
+            0x05 => &mut self.model_name,
+            0x06 => &mut self.user_application_name,
+            _ => {
+                self.extended.insert(id, value);
+                return;
+            }
+        };
+        *field = Some(String::from_utf8_lossy(&value).into_owned());
+    }
+}
@@ -1,8 +1,67 @@
-use crate::codec::request_to_bytesmut;
+use std::sync::Arc;
+
+use crate::{
+    codec::request_to_bytesmut,
+    util::{bytes_to_bits, bytes_to_registers, registers_to_bytes}
+};
 use bytes::BytesMut;
 use easy_modbus::*;
 
-mod codec;
+pub mod canopen;
+mod client;
+pub mod codec;
+mod custom;
+pub mod data;
+mod error;
+mod exception;
+mod frame;
+mod function;
+mod head;
+mod identification;
+mod register_map;
+mod retry;
+mod tid;
+pub mod transport;
+mod util;
+
+pub use canopen::{
+    CanopenGeneralReferenceRequest, CanopenGeneralReferenceResponse,
+    SdoResponse, MEI_CANOPEN_GENERAL_REFERENCE
+};
+#[cfg(feature = "tls")]
+pub use client::TlsClient;
+pub use client::{
+    Client, RtuOverTcpClient, RtuOverUdpClient, TcpClient, UdpClient
+};
+pub use custom::{CustomFunction, CustomRequest, CustomResponse};
+pub use data::{ModbusRegisters, RegisterValue, WordOrder};
+pub use error::Error;
+pub use exception::{Exception, ExceptionResponse};
+pub use frame::{
+    apply_mask, DiagnosticsRequest, DiagnosticsResponse,
+    DiagnosticsSubFunction, FileRecord, FileSubRequest,
+    GetCommEventCounterRequest, GetCommEventCounterResponse,
+    GetCommEventLogRequest, GetCommEventLogResponse,
+    MaskWriteRegisterRequest, MaskWriteRegisterResponse,
+    ReadDeviceIdCode, ReadDeviceIdentificationRequest,
+    ReadDeviceIdentificationResponse, ReadExceptionStatusRequest,
+    ReadExceptionStatusResponse, ReadFifoQueueRequest,
+    ReadFifoQueueResponse, ReadFileRecordRequest,
+    ReadFileRecordResponse, ReadWriteMultipleRegistersRequest,
+    ReadWriteMultipleRegistersResponse, ReportServerIdRequest,
+    ReportServerIdResponse, WriteFileRecordRequest,
+    WriteFileRecordResponse, FILE_MAX_RECORD_NUMBER,
+    FILE_RECORD_MAX_BYTES, FILE_REFERENCE_TYPE,
+    MEI_READ_DEVICE_IDENTIFICATION
+};
+pub use function::Function;
+pub use head::{Head, Version};
+pub use identification::DeviceIdentification;
+#[cfg(feature = "derive")]
+pub use modbus_client_derive::ModbusRegisters;
+pub use register_map::{Access, DataType, Point, RegisterMap, Table};
+pub use retry::RetryPolicy;
+pub use tid::TidAllocator;
 
 #[derive(Clone)]
 pub enum Request {
@@ -22,10 +81,35 @@ pub enum Request {
     WriteMultipleHoldingRegisters(
         Head,
         WriteMultipleHoldingRegistersRequest
-    )
+    ),
+    MaskWriteRegister(Head, MaskWriteRegisterRequest),
+    ReadWriteMultipleRegisters(
+        Head,
+        ReadWriteMultipleRegistersRequest
+    ),
+    ReadDeviceIdentification(Head, ReadDeviceIdentificationRequest),
+    ReadExceptionStatus(Head, ReadExceptionStatusRequest),
+    Diagnostics(Head, DiagnosticsRequest),
+    GetCommEventCounter(Head, GetCommEventCounterRequest),
+    GetCommEventLog(Head, GetCommEventLogRequest),
+    ReportServerId(Head, ReportServerIdRequest),
+    ReadFileRecord(Head, ReadFileRecordRequest),
+    WriteFileRecord(Head, WriteFileRecordRequest),
+    ReadFifoQueue(Head, ReadFifoQueueRequest),
+    Custom(Head, CustomRequest),
+    CanopenGeneralReference(Head, CanopenGeneralReferenceRequest)
 }
 
 impl Request {
+    /// Maximum quantity of coils or discrete inputs per read
+    pub const MAX_READ_BITS: u16 = 2000;
+    /// Maximum quantity of registers per read
+    pub const MAX_READ_REGISTERS: u16 = 125;
+    /// Maximum quantity of coils per write
+    pub const MAX_WRITE_BITS: u16 = 1968;
+    /// Maximum quantity of registers per write
+    pub const MAX_WRITE_REGISTERS: u16 = 123;
+
     pub fn head(&self) -> &Head {
         match self {
             Request::ReadCoils(head, _) => head,
@@ -35,48 +119,109 @@ impl Request {
             Request::WriteSingleCoil(head, _) => head,
             Request::WriteSingleHoldingRegister(head, _) => head,
             Request::WriteMultipleCoils(head, _) => head,
-            Request::WriteMultipleHoldingRegisters(head, _) => head
+            Request::WriteMultipleHoldingRegisters(head, _) => head,
+            Request::MaskWriteRegister(head, _) => head,
+            Request::ReadWriteMultipleRegisters(head, _) => head,
+            Request::ReadDeviceIdentification(head, _) => head,
+            Request::ReadExceptionStatus(head, _) => head,
+            Request::Diagnostics(head, _) => head,
+            Request::GetCommEventCounter(head, _) => head,
+            Request::GetCommEventLog(head, _) => head,
+            Request::ReportServerId(head, _) => head,
+            Request::ReadFileRecord(head, _) => head,
+            Request::WriteFileRecord(head, _) => head,
+            Request::ReadFifoQueue(head, _) => head,
+            Request::Custom(head, _) => head,
+            Request::CanopenGeneralReference(head, _) => head
+        }
+    }
+
+    /// Whether the request writes coils or registers
+    ///
+    /// User defined functions and CANopen general references other
+    /// than SDO uploads may write as well.
+    pub fn is_write(&self) -> bool {
+        match self {
+            Request::CanopenGeneralReference(_, body) => {
+                !canopen::is_sdo_upload(&body.payload)
+            },
+            request => matches!(
+                request,
+                Request::WriteSingleCoil(..)
+                    | Request::WriteSingleHoldingRegister(..)
+                    | Request::WriteMultipleCoils(..)
+                    | Request::WriteMultipleHoldingRegisters(..)
+                    | Request::MaskWriteRegister(..)
+                    | Request::ReadWriteMultipleRegisters(..)
+                    | Request::WriteFileRecord(..)
+                    | Request::Custom(..)
+            )
         }
     }
 
+    /// Whether the request addresses all servers of a serial line
+    pub fn is_broadcast(&self) -> bool {
+        self.head().uid == Head::BROADCAST_UID
+    }
+
     pub fn to_bytes(&self, data: &mut BytesMut) {
         request_to_bytesmut(self, data)
     }
 
-    /// Create a TCP frame
-    ///
-    /// A Modbus variant used for communications over TCP/IP networks.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use easy_modbus::Frame;
-    /// let tcp = Frame::tcp();
-    /// ```
-    // pub fn tcp() -> Frame {
-    //     Frame {
-    //         version: Version::Tcp,
-    //         tid_map: Mutex::new(HashMap::new()),
-    //     }
-    // }
+    pub fn head_mut(&mut self) -> &mut Head {
+        match self {
+            Request::ReadCoils(head, _) => head,
+            Request::ReadDiscreteInputs(head, _) => head,
+            Request::ReadMultipleHoldingRegisters(head, _) => head,
+            Request::ReadInputRegisters(head, _) => head,
+            Request::WriteSingleCoil(head, _) => head,
+            Request::WriteSingleHoldingRegister(head, _) => head,
+            Request::WriteMultipleCoils(head, _) => head,
+            Request::WriteMultipleHoldingRegisters(head, _) => head,
+            Request::MaskWriteRegister(head, _) => head,
+            Request::ReadWriteMultipleRegisters(head, _) => head,
+            Request::ReadDeviceIdentification(head, _) => head,
+            Request::ReadExceptionStatus(head, _) => head,
+            Request::Diagnostics(head, _) => head,
+            Request::GetCommEventCounter(head, _) => head,
+            Request::GetCommEventLog(head, _) => head,
+            Request::ReportServerId(head, _) => head,
+            Request::ReadFileRecord(head, _) => head,
+            Request::WriteFileRecord(head, _) => head,
+            Request::ReadFifoQueue(head, _) => head,
+            Request::Custom(head, _) => head,
+            Request::CanopenGeneralReference(head, _) => head
+        }
+    }
 
-    /// Create a RTU frame
+    /// Select the framing of the request
     ///
-    /// Used in serial communication, and is the most common
-    /// implementation available for Modbus.
+    /// Requests are built as RTU frames, use `Version::Tcp` to send
+    /// them with a MBAP header over TCP/IP networks or
+    /// `Version::Ascii` for modbus ASCII. Switching to TCP
+    /// allocates a transaction id from the global `TidAllocator`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let rut = Frame::rtu();
+    /// use modbus_client::{Request, Version};
+    /// let request = Request::read_coils_request(0x01, 0x02, 0x08)
+    ///     .with_version(Version::Tcp);
     /// ```
-    // pub fn rtu() -> Frame {
-    //     Frame {
-    //         version: Version::Rtu,
-    //         tid_map: Mutex::new(HashMap::new()),
-    //     }
-    // }
+    pub fn with_version(mut self, version: Version) -> Self {
+        let head = self.head_mut();
+        if head.version != version {
+            head.tid = Self::get_tid(version, head.uid);
+            head.version = version;
+        }
+        self
+    }
+
+    /// Set the transaction id of the request
+    pub fn with_tid(mut self, tid: u16) -> Self {
+        self.head_mut().tid = tid;
+        self
+    }
 
     /// Create a read coils request (Function Code: 0x01)
     ///
@@ -87,8 +232,8 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().read_coils_request(0x01, 0x02, 0x08);
+    /// use modbus_client::Request;
+    /// let request = Request::read_coils_request(0x01, 0x02, 0x08);
     /// ```
     pub fn read_coils_request(
         unit_id: u8,
@@ -98,12 +243,7 @@ impl Request {
         let function = Function::ReadCoils;
         let request_body =
             ReadCoilsRequest::new(first_address, number);
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::ReadCoils(head, request_body)
     }
 
@@ -116,11 +256,10 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().read_discrete_request(0x0B, 0x007A, 0x001C);
+    /// use modbus_client::Request;
+    /// let request = Request::read_discrete_request(0x0B, 0x007A, 0x001C);
     /// ```
     pub fn read_discrete_request(
-        &self,
         unit_id: u8,
         first_address: u16,
         number: u16
@@ -128,12 +267,7 @@ impl Request {
         let function = Function::ReadDiscreteInputs;
         let request_body =
             ReadDiscreteInputsRequest::new(first_address, number);
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::ReadDiscreteInputs(head, request_body)
     }
 
@@ -147,8 +281,8 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().read_multiple_holding_registers_request(0x0B, 0x006F, 0x0003);
+    /// use modbus_client::Request;
+    /// let request = Request::read_multiple_holding_registers_request(0x0B, 0x006F, 0x0003);
     /// ```
     pub fn read_multiple_holding_registers_request(
         unit_id: u8,
@@ -160,12 +294,7 @@ impl Request {
             first_address,
             number
         );
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::ReadMultipleHoldingRegisters(head, request_body)
     }
 
@@ -178,11 +307,10 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().read_input_registers_request(0x0B, 0x000A, 0x0001);
+    /// use modbus_client::Request;
+    /// let request = Request::read_input_registers_request(0x0B, 0x000A, 0x0001);
     /// ```
     pub fn read_input_registers_request(
-        &self,
         unit_id: u8,
         first_address: u16,
         number: u16
@@ -190,12 +318,7 @@ impl Request {
         let function = Function::ReadInputRegisters;
         let request_body =
             ReadInputRegistersRequest::new(first_address, number);
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::ReadInputRegisters(head, request_body)
     }
 
@@ -209,11 +332,10 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().write_single_coil_request(0x0B, 0x00BF, 0x0000);
+    /// use modbus_client::Request;
+    /// let request = Request::write_single_coil_request(0x0B, 0x00BF, 0x0000);
     /// ```
     pub fn write_single_coil_request(
-        &self,
         unit_id: u8,
         address: u16,
         value: u16
@@ -221,12 +343,7 @@ impl Request {
         let function = Function::WriteSingleCoil;
         let request_body =
             WriteSingleCoilRequest::new(address, value);
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::WriteSingleCoil(head, request_body)
     }
 
@@ -240,11 +357,10 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().write_single_holding_register_request(0x0B, 0x0004, 0xABCD);
+    /// use modbus_client::Request;
+    /// let request = Request::write_single_holding_register_request(0x0B, 0x0004, 0xABCD);
     /// ```
     pub fn write_single_holding_register_request(
-        &self,
         unit_id: u8,
         address: u16,
         value: u16
@@ -252,12 +368,7 @@ impl Request {
         let function = Function::WriteSingleHoldingRegister;
         let request_body =
             WriteSingleHoldingRegisterRequest::new(address, value);
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::WriteSingleHoldingRegister(head, request_body)
     }
 
@@ -272,8 +383,8 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().write_multiple_coils_request(
+    /// use modbus_client::Request;
+    /// let request = Request::write_multiple_coils_request(
     ///     0x0B,
     ///     0x001B,
     ///     0x0009,
@@ -281,7 +392,6 @@ impl Request {
     /// );
     /// ```
     pub fn write_multiple_coils_request(
-        &self,
         unit_id: u8,
         address: u16,
         coils_number: u16,
@@ -293,12 +403,7 @@ impl Request {
             coils_number,
             values
         );
-        let head = Self::init_head(
-            unit_id,
-            function,
-            request_body.len(),
-            false
-        );
+        let head = Self::init_head(unit_id, function, false);
         Request::WriteMultipleCoils(head, request_body)
     }
 
@@ -311,15 +416,14 @@ impl Request {
     /// # Examples
     ///
     /// ```
-    /// use easy_modbus::Frame;
-    /// let request = Frame::tcp().write_multiple_holding_registers_request(
+    /// use modbus_client::Request;
+    /// let request = Request::write_multiple_holding_registers_request(
     ///     0x0B,
     ///     0x0012,
     ///     vec![0x0B, 0x0A, 0xC1, 0x02],
     /// );
     /// ```
     pub fn write_multiple_holding_registers_request(
-        &self,
         unit_id: u8,
         address: u16,
         values: Vec<u8>
@@ -328,54 +432,470 @@ impl Request {
         let request_body = WriteMultipleHoldingRegistersRequest::new(
             address, values
         );
-        let head = Self::init_head(
+        let head = Self::init_head(unit_id, function, false);
+        Request::WriteMultipleHoldingRegisters(head, request_body)
+    }
+
+    /// Create a write multiple holding registers request of typed
+    /// values (Function Code: 0x10)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `address` - Address of first holding registers to write
+    /// * `values` - Values, each in `T::REGISTERS` registers
+    /// * `order` - Order of the bytes of a value in its registers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::{Request, WordOrder};
+    /// let request = Request::write_values_request(
+    ///     0x0B,
+    ///     0x0012,
+    ///     &[21.5f32, -3.0],
+    ///     WordOrder::Cdab
+    /// );
+    /// ```
+    pub fn write_values_request<T: RegisterValue>(
+        unit_id: u8,
+        address: u16,
+        values: &[T],
+        order: WordOrder
+    ) -> Request {
+        Self::write_multiple_holding_registers_request(
             unit_id,
+            address,
+            registers_to_bytes(&data::encode(values, order))
+        )
+    }
+
+    /// Create a mask write register request (Function Code: 0x16)
+    ///
+    /// The server sets the register to `(value & and_mask) |
+    /// (or_mask & !and_mask)`, see `apply_mask`.
+    ///
+    /// * `unit_id` -  Server address
+    /// * `address` - Address of holding register to modify
+    /// * `and_mask` - Bits to keep
+    /// * `or_mask` - Values of the bits not kept
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::mask_write_register_request(0x01, 0x0004, 0x00F2, 0x0025);
+    /// ```
+    pub fn mask_write_register_request(
+        unit_id: u8,
+        address: u16,
+        and_mask: u16,
+        or_mask: u16
+    ) -> Request {
+        let function = Function::MaskWriteRegister;
+        let request_body =
+            MaskWriteRegisterRequest::new(address, and_mask, or_mask);
+        let head = Self::init_head(unit_id, function, false);
+        Request::MaskWriteRegister(head, request_body)
+    }
+
+    /// Create a read/write multiple registers request (Function
+    /// Code: 0x17)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `read_address` - Address of first holding register to read
+    /// * `read_quantity` - Number of holding registers to read, at
+    ///   most 125
+    /// * `write_address` - Address of first holding register to write
+    /// * `values` - New values of holding registers, at most 121
+    ///   registers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::read_write_multiple_registers_request(
+    ///     0x01,
+    ///     0x0003,
+    ///     0x0006,
+    ///     0x000E,
+    ///     vec![0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF]
+    /// );
+    /// ```
+    pub fn read_write_multiple_registers_request(
+        unit_id: u8,
+        read_address: u16,
+        read_quantity: u16,
+        write_address: u16,
+        values: Vec<u8>
+    ) -> Request {
+        let function = Function::ReadWriteMultipleRegisters;
+        let request_body = ReadWriteMultipleRegistersRequest::new(
+            read_address,
+            read_quantity,
+            write_address,
+            values
+        );
+        let head = Self::init_head(unit_id, function, false);
+        Request::ReadWriteMultipleRegisters(head, request_body)
+    }
+
+    /// Create a read device identification request (Function Code:
+    /// 0x2B, MEI type: 0x0E)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `read_device_id_code` - Access type
+    /// * `object_id` - Object to start the stream with, 0x00 for the
+    ///   first page, or the object to read for individual access
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::{ReadDeviceIdCode, Request};
+    /// let request = Request::read_device_identification_request(
+    ///     0x01,
+    ///     ReadDeviceIdCode::Basic,
+    ///     0x00
+    /// );
+    /// ```
+    pub fn read_device_identification_request(
+        unit_id: u8,
+        read_device_id_code: ReadDeviceIdCode,
+        object_id: u8
+    ) -> Request {
+        let function = Function::EncapsulatedInterfaceTransport;
+        let request_body = ReadDeviceIdentificationRequest::new(
+            read_device_id_code,
+            object_id
+        );
+        let head = Self::init_head(unit_id, function, false);
+        Request::ReadDeviceIdentification(head, request_body)
+    }
+
+    /// Create a read exception status request (Function Code: 0x07)
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::read_exception_status_request(0x11);
+    /// ```
+    pub fn read_exception_status_request(unit_id: u8) -> Request {
+        let function = Function::ReadExceptionStatus;
+        let head = Self::init_head(unit_id, function, false);
+        Request::ReadExceptionStatus(head, ReadExceptionStatusRequest)
+    }
+
+    /// Create a diagnostics request (Function Code: 0x08)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `sub_function` - Diagnostic to run
+    /// * `data` - Data field, two bytes for all sub-functions but
+    ///   Return Query Data
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::{DiagnosticsSubFunction, Request};
+    /// let request = Request::diagnostics_request(
+    ///     0x11,
+    ///     DiagnosticsSubFunction::ReturnQueryData,
+    ///     vec![0xA5, 0x37]
+    /// );
+    /// ```
+    pub fn diagnostics_request(
+        unit_id: u8,
+        sub_function: DiagnosticsSubFunction,
+        data: Vec<u8>
+    ) -> Request {
+        let function = Function::Diagnostics;
+        let request_body =
+            DiagnosticsRequest::new(sub_function, data);
+        let head = Self::init_head(unit_id, function, false);
+        Request::Diagnostics(head, request_body)
+    }
+
+    /// Create a get comm event counter request (Function Code: 0x0B)
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::get_comm_event_counter_request(0x11);
+    /// ```
+    pub fn get_comm_event_counter_request(unit_id: u8) -> Request {
+        let function = Function::GetCommEventCounter;
+        let head = Self::init_head(unit_id, function, false);
+        Request::GetCommEventCounter(head, GetCommEventCounterRequest)
+    }
+
+    /// Create a get comm event log request (Function Code: 0x0C)
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::get_comm_event_log_request(0x11);
+    /// ```
+    pub fn get_comm_event_log_request(unit_id: u8) -> Request {
+        let function = Function::GetCommEventLog;
+        let head = Self::init_head(unit_id, function, false);
+        Request::GetCommEventLog(head, GetCommEventLogRequest)
+    }
+
+    /// Create a report server id request (Function Code: 0x11)
+    ///
+    /// * `unit_id` -  Server address
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::report_server_id_request(0x11);
+    /// ```
+    pub fn report_server_id_request(unit_id: u8) -> Request {
+        let function = Function::ReportServerId;
+        let head = Self::init_head(unit_id, function, false);
+        Request::ReportServerId(head, ReportServerIdRequest)
+    }
+
+    /// Create a read file record request (Function Code: 0x14)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `sub_requests` - Records to read, the request and the
+    ///   response must not exceed `FILE_RECORD_MAX_BYTES` and the
+    ///   records `FILE_MAX_RECORD_NUMBER`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::{FileSubRequest, Request};
+    /// let request = Request::read_file_record_request(
+    ///     0x01,
+    ///     vec![
+    ///         FileSubRequest::new(0x0004, 0x0001, 0x0002),
+    ///         FileSubRequest::new(0x0003, 0x0009, 0x0002),
+    ///     ]
+    /// );
+    /// ```
+    pub fn read_file_record_request(
+        unit_id: u8,
+        sub_requests: Vec<FileSubRequest>
+    ) -> Request {
+        let function = Function::ReadFileRecord;
+        let request_body = ReadFileRecordRequest::new(sub_requests);
+        let head = Self::init_head(unit_id, function, false);
+        Request::ReadFileRecord(head, request_body)
+    }
+
+    /// Create a write file record request (Function Code: 0x15)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `records` - Records to write, the request must not exceed
+    ///   `FILE_RECORD_MAX_BYTES` and the records
+    ///   `FILE_MAX_RECORD_NUMBER`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::{FileRecord, Request};
+    /// let request = Request::write_file_record_request(
+    ///     0x01,
+    ///     vec![FileRecord::new(0x0004, 0x0007, vec![0x06AF, 0x04BE, 0x100D])]
+    /// );
+    /// ```
+    pub fn write_file_record_request(
+        unit_id: u8,
+        records: Vec<FileRecord>
+    ) -> Request {
+        let function = Function::WriteFileRecord;
+        let request_body = WriteFileRecordRequest::new(records);
+        let head = Self::init_head(unit_id, function, false);
+        Request::WriteFileRecord(head, request_body)
+    }
+
+    /// Create a read FIFO queue request (Function Code: 0x18)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `fifo_address` - Address of the FIFO pointer register
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::read_fifo_queue_request(0x01, 0x04DE);
+    /// ```
+    pub fn read_fifo_queue_request(
+        unit_id: u8,
+        fifo_address: u16
+    ) -> Request {
+        let function = Function::ReadFifoQueue;
+        let request_body = ReadFifoQueueRequest::new(fifo_address);
+        let head = Self::init_head(unit_id, function, false);
+        Request::ReadFifoQueue(head, request_body)
+    }
+
+    /// Create a request of a user defined function code
+    ///
+    /// * `unit_id` -  Server address
+    /// * `function` - User defined function code, see
+    ///   `Function::is_user_defined`
+    /// * `payload` - Data following the function code
+    /// * `response_len` - Length of the response data, see
+    ///   `CustomFunction`
+    ///
+    /// Other function codes fail with `Error::InvalidFunction`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// // fixed response length of 4 bytes
+    /// let request = Request::custom_request(
+    ///     0x01,
+    ///     0x64,
+    ///     vec![0x01, 0x02],
+    ///     |_: &[u8], _: &[u8]| Some(4)
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn custom_request(
+        unit_id: u8,
+        function: u8,
+        payload: Vec<u8>,
+        response_len: impl CustomFunction + 'static
+    ) -> Result<Request, Error> {
+        if !Function::is_user_defined(function) {
+            return Err(Error::InvalidFunction(function));
+        }
+        let request_body = CustomRequest::new(
             function,
-            request_body.len(),
+            payload,
+            Arc::new(response_len)
+        );
+        let head = Self::init_head(
+            unit_id,
+            Function::Custom(function),
             false
         );
-        Request::WriteMultipleHoldingRegisters(head, request_body)
+        Ok(Request::Custom(head, request_body))
+    }
+
+    /// Create a CANopen general reference request (Function Code:
+    /// 0x2B, MEI type: 0x0D)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `payload` - Data following the MEI type
+    /// * `response_len` - Length of the response payload following
+    ///   the MEI type, see `CustomFunction`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::{canopen, Request};
+    /// // read object 0x1018/1 (vendor id)
+    /// let request = Request::canopen_general_reference_request(
+    ///     0x02,
+    ///     canopen::sdo_upload(0x1018, 0x01),
+    ///     |_: &[u8], _: &[u8]| Some(canopen::SDO_LEN)
+    /// );
+    /// ```
+    pub fn canopen_general_reference_request(
+        unit_id: u8,
+        payload: Vec<u8>,
+        response_len: impl CustomFunction + 'static
+    ) -> Request {
+        let function = Function::EncapsulatedInterfaceTransport;
+        let request_body = CanopenGeneralReferenceRequest::new(
+            payload,
+            Arc::new(response_len)
+        );
+        let head = Self::init_head(unit_id, function, false);
+        Request::CanopenGeneralReference(head, request_body)
+    }
+
+    /// Create an expedited SDO upload request of a CANopen object
+    /// (Function Code: 0x2B, MEI type: 0x0D)
+    ///
+    /// * `unit_id` -  Server address
+    /// * `index` - Object index
+    /// * `subindex` - Object subindex
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request = Request::sdo_upload_request(0x02, 0x1018, 0x01);
+    /// ```
+    pub fn sdo_upload_request(
+        unit_id: u8,
+        index: u16,
+        subindex: u8
+    ) -> Request {
+        Self::canopen_general_reference_request(
+            unit_id,
+            canopen::sdo_upload(index, subindex),
+            canopen::sdo_response_len
+        )
+    }
+
+    /// Create an expedited SDO download request of a CANopen object
+    /// (Function Code: 0x2B, MEI type: 0x0D), `None` unless `data`
+    /// has 1 to 4 bytes
+    ///
+    /// * `unit_id` -  Server address
+    /// * `index` - Object index
+    /// * `subindex` - Object subindex
+    /// * `data` - Object value, least significant byte first
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use modbus_client::Request;
+    /// let request =
+    ///     Request::sdo_download_request(0x02, 0x6040, 0x00, &[0x0F, 0x00]);
+    /// assert!(request.is_some());
+    /// ```
+    pub fn sdo_download_request(
+        unit_id: u8,
+        index: u16,
+        subindex: u8,
+        data: &[u8]
+    ) -> Option<Request> {
+        let payload = canopen::sdo_download(index, subindex, data)?;
+        Some(Self::canopen_general_reference_request(
+            unit_id,
+            payload,
+            canopen::sdo_response_len
+        ))
     }
 
     /// Build modbus message head
     fn init_head(
         uid: u8,
         function: Function,
-        body_length: u16,
         is_exception: bool
     ) -> Head {
-        // todo
         Head::new(
-            Self::get_tid(uid),
+            Self::get_tid(Version::Rtu, uid),
             uid,
             function,
-            body_length,
             Version::Rtu,
             is_exception
         )
     }
 
-    /// Get tid by uid from tid_map
-    fn get_tid(_unit_id: u8) -> u16 {
-        return 0;
-        // todo
-        // if self.version == Version::Rtu {
-        //     return 0;
-        // }
-
-        // let mut map = self.tid_map.lock().unwrap();
-        // let value = match map.get(&unit_id) {
-        //     None => 1,
-        //     Some(v) => {
-        //         if v < &0xFFFF {
-        //             v + 1
-        //         } else {
-        //             1
-        //         }
-        //     }
-        // };
-        // map.insert(unit_id, value);
-        // value
+    /// Get tid by uid from the global allocator, RTU and ASCII frames
+    /// have no tid
+    fn get_tid(version: Version, unit_id: u8) -> u16 {
+        match version {
+            Version::Tcp => TidAllocator::global().next(unit_id),
+            Version::Rtu | Version::Ascii => 0
+        }
     }
 }
 
@@ -425,5 +945,332 @@ pub enum Response {
             WriteMultipleHoldingRegistersResponse,
             ExceptionResponse
         >
+    ),
+    MaskWriteRegister(
+        Head,
+        MaskWriteRegisterRequest,
+        Result<MaskWriteRegisterResponse, ExceptionResponse>
+    ),
+    ReadWriteMultipleRegisters(
+        Head,
+        ReadWriteMultipleRegistersRequest,
+        Result<ReadWriteMultipleRegistersResponse, ExceptionResponse>
+    ),
+    ReadDeviceIdentification(
+        Head,
+        ReadDeviceIdentificationRequest,
+        Result<ReadDeviceIdentificationResponse, ExceptionResponse>
+    ),
+    ReadExceptionStatus(
+        Head,
+        ReadExceptionStatusRequest,
+        Result<ReadExceptionStatusResponse, ExceptionResponse>
+    ),
+    Diagnostics(
+        Head,
+        DiagnosticsRequest,
+        Result<DiagnosticsResponse, ExceptionResponse>
+    ),
+    GetCommEventCounter(
+        Head,
+        GetCommEventCounterRequest,
+        Result<GetCommEventCounterResponse, ExceptionResponse>
+    ),
+    GetCommEventLog(
+        Head,
+        GetCommEventLogRequest,
+        Result<GetCommEventLogResponse, ExceptionResponse>
+    ),
+    ReportServerId(
+        Head,
+        ReportServerIdRequest,
+        Result<ReportServerIdResponse, ExceptionResponse>
+    ),
+    ReadFileRecord(
+        Head,
+        ReadFileRecordRequest,
+        Result<ReadFileRecordResponse, ExceptionResponse>
+    ),
+    WriteFileRecord(
+        Head,
+        WriteFileRecordRequest,
+        Result<WriteFileRecordResponse, ExceptionResponse>
+    ),
+    ReadFifoQueue(
+        Head,
+        ReadFifoQueueRequest,
+        Result<ReadFifoQueueResponse, ExceptionResponse>
+    ),
+    Custom(
+        Head,
+        CustomRequest,
+        Result<CustomResponse, ExceptionResponse>
+    ),
+    CanopenGeneralReference(
+        Head,
+        CanopenGeneralReferenceRequest,
+        Result<CanopenGeneralReferenceResponse, ExceptionResponse>
     )
 }
+
+impl Response {
+    pub fn head(&self) -> &Head {
+        match self {
+            Response::ReadCoils(head, ..) => head,
+            Response::ReadDiscreteInputs(head, ..) => head,
+            Response::ReadMultipleHoldingRegisters(head, ..) => head,
+            Response::ReadInputRegisters(head, ..) => head,
+            Response::WriteSingleCoil(head, ..) => head,
+            Response::WriteSingleHoldingRegister(head, ..) => head,
+            Response::WriteMultipleCoils(head, ..) => head,
+            Response::WriteMultipleHoldingRegisters(head, ..) => head,
+            Response::MaskWriteRegister(head, ..) => head,
+            Response::ReadWriteMultipleRegisters(head, ..) => head,
+            Response::ReadDeviceIdentification(head, ..) => head,
+            Response::ReadExceptionStatus(head, ..) => head,
+            Response::Diagnostics(head, ..) => head,
+            Response::GetCommEventCounter(head, ..) => head,
+            Response::GetCommEventLog(head, ..) => head,
+            Response::ReportServerId(head, ..) => head,
+            Response::ReadFileRecord(head, ..) => head,
+            Response::WriteFileRecord(head, ..) => head,
+            Response::ReadFifoQueue(head, ..) => head,
+            Response::Custom(head, ..) => head,
+            Response::CanopenGeneralReference(head, ..) => head
+        }
+    }
+
+    /// Unit id of the responding server
+    pub fn unit_id(&self) -> u8 {
+        self.head().uid
+    }
+
+    /// Function of the request, the exception flag isn't included
+    pub fn function(&self) -> Function {
+        self.head().function
+    }
+
+    /// Registers read by a read holding/input registers, read/write
+    /// multiple registers or read FIFO queue request, `None` for
+    /// other responses and exception responses
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{Request, TcpClient};
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let request =
+    ///     Request::read_multiple_holding_registers_request(0x01, 0x00, 2);
+    /// let registers = client.call(request).await?.into_result()?.registers();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn registers(&self) -> Option<Vec<u16>> {
+        let bytes = match self {
+            Response::ReadMultipleHoldingRegisters(_, _, Ok(rs)) => {
+                rs.get_values()
+            },
+            Response::ReadInputRegisters(_, _, Ok(rs)) => {
+                rs.get_values()
+            },
+            Response::ReadWriteMultipleRegisters(_, _, Ok(rs)) => {
+                rs.get_values()
+            },
+            Response::ReadFifoQueue(_, _, Ok(rs)) => rs.get_values(),
+            _ => return None
+        };
+        Some(bytes_to_registers(&bytes))
+    }
+
+    /// Registers decoded as values of type `T`, see `registers`.
+    /// `None` unless they hold a whole number of values.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::{Request, TcpClient, WordOrder};
+    ///
+    /// # async fn run() -> Result<(), modbus_client::Error> {
+    /// let mut client = TcpClient::connect("127.0.0.1:502").await?;
+    /// let request =
+    ///     Request::read_input_registers_request(0x01, 0x00, 4);
+    /// let values = client
+    ///     .call(request)
+    ///     .await?
+    ///     .values::<f32>(WordOrder::Abcd);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn values<T: RegisterValue>(
+        &self,
+        order: WordOrder
+    ) -> Option<Vec<T>> {
+        data::decode(&self.registers()?, order)
+    }
+
+    /// Coils or discrete inputs read, trimmed to the quantity of the
+    /// request, `None` for other responses and exception responses
+    pub fn bits(&self) -> Option<Vec<bool>> {
+        let (request, bytes) = match self {
+            Response::ReadCoils(_, rq, Ok(rs)) => {
+                (BytesMut::from(rq.clone()), rs.get_values())
+            },
+            Response::ReadDiscreteInputs(_, rq, Ok(rs)) => {
+                (BytesMut::from(rq.clone()), rs.get_values())
+            },
+            _ => return None
+        };
+        // starting address followed by the quantity
+        let quantity = u16::from_be_bytes([request[2], request[3]]);
+        Some(bytes_to_bits(&bytes, quantity))
+    }
+
+    /// `Error::Exception` for exception responses
+    pub fn into_result(self) -> Result<Response, Error> {
+        match self.exception() {
+            Some(rs) => Err(Error::from(rs.clone())),
+            None => Ok(self)
+        }
+    }
+
+    /// The exception of an exception response
+    pub fn exception(&self) -> Option<&ExceptionResponse> {
+        match self {
+            Response::ReadCoils(_, _, rs) => rs.as_ref().err(),
+            Response::ReadDiscreteInputs(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::ReadMultipleHoldingRegisters(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::ReadInputRegisters(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::WriteSingleCoil(_, _, rs) => rs.as_ref().err(),
+            Response::WriteSingleHoldingRegister(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::WriteMultipleCoils(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::WriteMultipleHoldingRegisters(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::MaskWriteRegister(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::ReadWriteMultipleRegisters(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::ReadDeviceIdentification(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::ReadExceptionStatus(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::Diagnostics(_, _, rs) => rs.as_ref().err(),
+            Response::GetCommEventCounter(_, _, rs) => {
+                rs.as_ref().err()
+            },
+            Response::GetCommEventLog(_, _, rs) => rs.as_ref().err(),
+            Response::ReportServerId(_, _, rs) => rs.as_ref().err(),
+            Response::ReadFileRecord(_, _, rs) => rs.as_ref().err(),
+            Response::WriteFileRecord(_, _, rs) => rs.as_ref().err(),
+            Response::ReadFifoQueue(_, _, rs) => rs.as_ref().err(),
+            Response::Custom(_, _, rs) => rs.as_ref().err(),
+            Response::CanopenGeneralReference(_, _, rs) => {
+                rs.as_ref().err()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::*;
+    use crate::codec::TcpCodec;
+
+    /// Response to `request` of unit id 1 carrying `pdu`
+    fn response(request: Request, pdu: &[u8]) -> Response {
+        let mut codec = TcpCodec::default();
+        codec.encode(request, &mut BytesMut::new()).unwrap();
+        let length = pdu.len() as u16 + 1;
+        let mut src = BytesMut::from(&[0x00, 0x01, 0x00, 0x00][..]);
+        src.extend_from_slice(&length.to_be_bytes());
+        src.extend_from_slice(&[0x01]);
+        src.extend_from_slice(pdu);
+        codec.decode(&mut src).unwrap().unwrap()
+    }
+
+    #[test]
+    fn bits_trimmed_to_quantity() {
+        let rs = response(
+            Request::read_coils_request(0x01, 0x0013, 10),
+            &[0x01, 0x02, 0xCD, 0x01]
+        );
+        assert_eq!(
+            rs.bits(),
+            Some(vec![
+                true, false, true, true, false, false, true, true,
+                true, false
+            ])
+        );
+        let rs = response(
+            Request::read_discrete_request(0x01, 0x00C4, 3),
+            &[0x02, 0x01, 0xFD]
+        );
+        assert_eq!(rs.bits(), Some(vec![true, false, true]));
+        let rs = response(
+            Request::read_coils_request(0x01, 0x0000, 16),
+            &[0x01, 0x02, 0x00, 0x80]
+        );
+        let bits = rs.bits().unwrap();
+        assert_eq!(bits.len(), 16);
+        assert_eq!(bits.iter().position(|bit| *bit), Some(15));
+    }
+
+    #[test]
+    fn bits_of_other_responses() {
+        let rs = response(
+            Request::read_multiple_holding_registers_request(
+                0x01, 0x0000, 1
+            ),
+            &[0x03, 0x02, 0x00, 0x01]
+        );
+        assert_eq!(rs.bits(), None);
+        let rs = response(
+            Request::read_coils_request(0x01, 0x0000, 8),
+            &[0x81, 0x02]
+        );
+        assert_eq!(rs.bits(), None);
+    }
+
+    #[test]
+    fn exception_response() {
+        let rs = response(
+            Request::read_coils_request(0x01, 0x0000, 8),
+            &[0x81, 0x02]
+        );
+        assert_eq!(
+            rs.exception().map(|rs| rs.exception),
+            Some(Exception::IllegalDataAddress)
+        );
+        assert!(matches!(
+            rs.into_result(),
+            Err(Error::Exception(Exception::IllegalDataAddress))
+        ));
+
+        let rs = response(
+            Request::write_single_holding_register_request(
+                0x01, 0x0001, 0x0003
+            ),
+            &[0x06, 0x00, 0x01, 0x00, 0x03]
+        );
+        assert!(rs.exception().is_none());
+        assert!(rs.into_result().is_ok());
+    }
+}
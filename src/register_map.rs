@@ -0,0 +1,549 @@
+//! Register maps: the named points of a device model
+//!
+//! A point describes where a value is stored (table and address),
+//! how it is encoded (data type and word order) and how the raw value
+//! is scaled into engineering units. Maps can be loaded from TOML
+//! (feature `toml`), JSON (feature `json`) or CSV (feature `csv`)
+//! files, any other serde format works with the `serde` feature.
+//!
+//! TOML and JSON files hold a list of `points`, CSV files a row per
+//! point with the field names as header. Empty CSV cells and missing
+//! fields take the defaults: word order ABCD, scale 1, offset 0, no
+//! unit and read access.
+//!
+//! ```toml
+//! [[points]]
+//! name = "active_power"
+//! table = "holding"
+//! address = 0x0010
+//! data_type = "f32"
+//! word_order = "CDAB"
+//! unit = "kW"
+//!
+//! [[points]]
+//! name = "frequency"
+//! table = "input"
+//! address = 0x0020
+//! data_type = "u16"
+//! scale = 0.01
+//! unit = "Hz"
+//! ```
+//!
+//! ```text
+//! name,table,address,data_type,word_order,scale,offset,unit,access
+//! active_power,holding,16,f32,CDAB,,,kW,
+//! frequency,input,32,u16,,0.01,,Hz,read
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    data::{RegisterValue, WordOrder},
+    Error, Request, Response
+};
+
+/// Table a point is stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Table {
+    Coil,
+    Discrete,
+    Input,
+    Holding
+}
+
+impl Table {
+    /// Coils and discrete inputs hold bits, the others registers
+    pub fn is_bit(&self) -> bool {
+        matches!(self, Table::Coil | Table::Discrete)
+    }
+
+    /// Discrete inputs and input registers can't be written
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Table::Coil | Table::Holding)
+    }
+}
+
+/// Data type of a point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum DataType {
+    /// A coil or discrete input
+    Bool,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64
+}
+
+impl DataType {
+    /// Number of coils/discrete inputs or registers of a value
+    pub fn quantity(&self) -> u16 {
+        match self {
+            DataType::Bool => 1,
+            DataType::U16 => u16::REGISTERS as u16,
+            DataType::I16 => i16::REGISTERS as u16,
+            DataType::U32 => u32::REGISTERS as u16,
+            DataType::I32 => i32::REGISTERS as u16,
+            DataType::F32 => f32::REGISTERS as u16,
+            DataType::U64 => u64::REGISTERS as u16,
+            DataType::I64 => i64::REGISTERS as u16,
+            DataType::F64 => f64::REGISTERS as u16
+        }
+    }
+
+    /// Decode a raw value from registers
+    fn decode(
+        &self,
+        registers: &[u16],
+        order: WordOrder
+    ) -> Option<f64> {
+        let value = match self {
+            DataType::Bool => return None,
+            DataType::U16 => {
+                u16::from_registers(registers, order)? as f64
+            },
+            DataType::I16 => {
+                i16::from_registers(registers, order)? as f64
+            },
+            DataType::U32 => {
+                u32::from_registers(registers, order)? as f64
+            },
+            DataType::I32 => {
+                i32::from_registers(registers, order)? as f64
+            },
+            DataType::F32 => {
+                f32::from_registers(registers, order)? as f64
+            },
+            DataType::U64 => {
+                u64::from_registers(registers, order)? as f64
+            },
+            DataType::I64 => {
+                i64::from_registers(registers, order)? as f64
+            },
+            DataType::F64 => f64::from_registers(registers, order)?
+        };
+        Some(value)
+    }
+}
+
+/// Access to a point
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Access {
+    #[default]
+    Read,
+    Write,
+    ReadWrite
+}
+
+impl Access {
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Access::Read | Access::ReadWrite)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Access::Write | Access::ReadWrite)
+    }
+}
+
+/// A named value of a device
+///
+/// The value in engineering units is `raw * scale + offset`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub struct Point {
+    pub name:       String,
+    pub table:      Table,
+    /// Address of the first coil/discrete input or register
+    pub address:    u16,
+    pub data_type:  DataType,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub word_order: WordOrder,
+    #[cfg_attr(feature = "serde", serde(default = "default_scale"))]
+    pub scale:      f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset:     f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unit:       Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub access:     Access
+}
+
+#[cfg(feature = "serde")]
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl Point {
+    /// Unscaled point of `data_type` with read access
+    pub fn new(
+        name: &str,
+        table: Table,
+        address: u16,
+        data_type: DataType
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            table,
+            address,
+            data_type,
+            word_order: WordOrder::default(),
+            scale: 1.0,
+            offset: 0.0,
+            unit: None,
+            access: Access::default()
+        }
+    }
+
+    /// Read request of the point
+    pub fn read_request(&self, unit_id: u8) -> Request {
+        let quantity = self.data_type.quantity();
+        match self.table {
+            Table::Coil => Request::read_coils_request(
+                unit_id,
+                self.address,
+                quantity
+            ),
+            Table::Discrete => Request::read_discrete_request(
+                unit_id,
+                self.address,
+                quantity
+            ),
+            Table::Input => Request::read_input_registers_request(
+                unit_id,
+                self.address,
+                quantity
+            ),
+            Table::Holding => {
+                Request::read_multiple_holding_registers_request(
+                    unit_id,
+                    self.address,
+                    quantity
+                )
+            },
+        }
+    }
+
+    /// Scaled value of the point from the response to its read
+    /// request, bits are 0 or 1. `None` if the response doesn't fit.
+    pub fn decode(&self, response: &Response) -> Option<f64> {
+        let raw = if self.table.is_bit() {
+            let bits = response.bits()?;
+            match bits[..] {
+                [bit] => bit as u8 as f64,
+                _ => return None
+            }
+        } else {
+            let registers = response.registers()?;
+            self.data_type.decode(&registers, self.word_order)?
+        };
+        Some(raw * self.scale + self.offset)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.table.is_bit() != (self.data_type == DataType::Bool) {
+            return Err(format!(
+                "{:?} can't be stored in {:?}",
+                self.data_type, self.table
+            ));
+        }
+        if self.access.is_writable() && !self.table.is_writable() {
+            return Err(format!("{:?} is read only", self.table));
+        }
+        if !self.scale.is_finite()
+            || self.scale == 0.0
+            || !self.offset.is_finite()
+        {
+            return Err("invalid scale or offset".to_string());
+        }
+        if self.end() > u16::MAX as u32 {
+            return Err("address out of range".to_string());
+        }
+        Ok(())
+    }
+
+    /// Last address of the point
+    fn end(&self) -> u32 {
+        self.address as u32 + self.data_type.quantity() as u32 - 1
+    }
+}
+
+/// Points of a device model by name
+///
+/// # Examples
+///
+/// ```
+/// use modbus_client::{DataType, Point, RegisterMap, Table};
+///
+/// let mut power = Point::new("active_power", Table::Holding, 16, DataType::I32);
+/// power.scale = 0.1;
+/// let map = RegisterMap::new(vec![
+///     power,
+///     Point::new("running", Table::Coil, 0, DataType::Bool),
+/// ])
+/// .unwrap();
+/// assert_eq!(map.get("active_power").unwrap().address, 16);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RegisterMap {
+    points: Vec<Point>,
+    names:  HashMap<String, usize>
+}
+
+#[cfg(any(feature = "toml", feature = "json"))]
+#[derive(serde::Deserialize)]
+struct MapFile {
+    points: Vec<Point>
+}
+
+impl RegisterMap {
+    /// Validate the points: unique names, data types fitting their
+    /// table, addresses within the 16 bit range and no overlapping
+    /// points in a table
+    pub fn new(points: Vec<Point>) -> Result<Self, Error> {
+        let invalid =
+            |point: &Point, reason: String| Error::InvalidPoint {
+                name: point.name.clone(),
+                reason
+            };
+        let mut names = HashMap::new();
+        for (i, point) in points.iter().enumerate() {
+            point.check().map_err(|reason| invalid(point, reason))?;
+            if names.insert(point.name.clone(), i).is_some() {
+                return Err(invalid(
+                    point,
+                    "duplicate name".to_string()
+                ));
+            }
+        }
+        let mut sorted: Vec<&Point> = points.iter().collect();
+        sorted
+            .sort_by_key(|point| (point.table as u8, point.address));
+        for pair in sorted.windows(2) {
+            if pair[0].table == pair[1].table
+                && pair[1].address as u32 <= pair[0].end()
+            {
+                return Err(invalid(
+                    pair[1],
+                    format!("overlaps {}", pair[0].name)
+                ));
+            }
+        }
+        Ok(Self { points, names })
+    }
+
+    /// Load a map from the contents of a TOML file
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, Error> {
+        let file: MapFile = toml::from_str(toml)
+            .map_err(|e| Error::InvalidMap(e.to_string()))?;
+        Self::new(file.points)
+    }
+
+    /// Load a map from the contents of a JSON file
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let file: MapFile = serde_json::from_str(json)
+            .map_err(|e| Error::InvalidMap(e.to_string()))?;
+        Self::new(file.points)
+    }
+
+    /// Load a map from the contents of a CSV file with a header row
+    #[cfg(feature = "csv")]
+    pub fn from_csv(csv: &str) -> Result<Self, Error> {
+        let points = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(csv.as_bytes())
+            .deserialize::<CsvPoint>()
+            .map(|row| row.map(Point::from))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::InvalidMap(e.to_string()))?;
+        Self::new(points)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Point> {
+        self.names.get(name).map(|&i| &self.points[i])
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+}
+
+/// CSV row of a point, empty cells take the defaults
+#[cfg(feature = "csv")]
+#[derive(serde::Deserialize)]
+struct CsvPoint {
+    name:       String,
+    table:      Table,
+    address:    u16,
+    data_type:  DataType,
+    word_order: Option<WordOrder>,
+    scale:      Option<f64>,
+    offset:     Option<f64>,
+    unit:       Option<String>,
+    access:     Option<Access>
+}
+
+#[cfg(feature = "csv")]
+impl From<CsvPoint> for Point {
+    fn from(row: CsvPoint) -> Self {
+        Self {
+            name:       row.name,
+            table:      row.table,
+            address:    row.address,
+            data_type:  row.data_type,
+            word_order: row.word_order.unwrap_or_default(),
+            scale:      row.scale.unwrap_or(1.0),
+            offset:     row.offset.unwrap_or(0.0),
+            unit:       row.unit,
+            access:     row.access.unwrap_or_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::*;
+    use crate::codec::TcpCodec;
+
+    /// Response of unit id 1 carrying `pdu` to the read request of
+    /// `point`
+    fn response(point: &Point, pdu: &[u8]) -> Response {
+        let mut codec = TcpCodec::default();
+        codec
+            .encode(point.read_request(0x01), &mut BytesMut::new())
+            .unwrap();
+        let length = pdu.len() as u16 + 1;
+        let mut src = BytesMut::from(&[0x00, 0x01, 0x00, 0x00][..]);
+        src.extend_from_slice(&length.to_be_bytes());
+        src.extend_from_slice(&[0x01]);
+        src.extend_from_slice(pdu);
+        codec.decode(&mut src).unwrap().unwrap()
+    }
+
+    fn assert_close(value: Option<f64>, expected: f64) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() < 1e-9, "{}", value);
+    }
+
+    fn invalid_point(points: Vec<Point>) -> String {
+        match RegisterMap::new(points) {
+            Err(Error::InvalidPoint { name, .. }) => name,
+            rs => panic!("{:?}", rs)
+        }
+    }
+
+    #[test]
+    fn overlapping_points() {
+        let power =
+            Point::new("power", Table::Holding, 0, DataType::U32);
+        // adjacent and in another table
+        let map = RegisterMap::new(vec![
+            power.clone(),
+            Point::new("voltage", Table::Holding, 2, DataType::U16),
+            Point::new("current", Table::Input, 1, DataType::U16),
+        ])
+        .unwrap();
+        assert_eq!(map.points().len(), 3);
+        let name = invalid_point(vec![
+            power,
+            Point::new("voltage", Table::Holding, 1, DataType::U16),
+        ]);
+        assert_eq!(name, "voltage");
+    }
+
+    #[test]
+    fn points_out_of_range() {
+        let last =
+            Point::new("last", Table::Holding, 0xFFFE, DataType::U32);
+        assert!(RegisterMap::new(vec![last]).is_ok());
+        let past =
+            Point::new("past", Table::Holding, 0xFFFF, DataType::U32);
+        assert_eq!(invalid_point(vec![past]), "past");
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let name = invalid_point(vec![
+            Point::new("power", Table::Holding, 0, DataType::U16),
+            Point::new("power", Table::Holding, 5, DataType::U16),
+        ]);
+        assert_eq!(name, "power");
+    }
+
+    #[test]
+    fn data_type_of_table() {
+        let bits = Point::new("bits", Table::Coil, 0, DataType::U16);
+        assert_eq!(invalid_point(vec![bits]), "bits");
+        let flag =
+            Point::new("flag", Table::Input, 0, DataType::Bool);
+        assert_eq!(invalid_point(vec![flag]), "flag");
+        let mut setpoint =
+            Point::new("setpoint", Table::Input, 0, DataType::U16);
+        setpoint.access = Access::Write;
+        assert_eq!(invalid_point(vec![setpoint]), "setpoint");
+        let mut scaled =
+            Point::new("scaled", Table::Input, 0, DataType::U16);
+        scaled.scale = 0.0;
+        assert_eq!(invalid_point(vec![scaled]), "scaled");
+    }
+
+    #[test]
+    fn decode_scaled_points() {
+        let mut temperature =
+            Point::new("temperature", Table::Input, 0, DataType::I16);
+        temperature.scale = 0.1;
+        temperature.offset = 273.15;
+        // -123
+        let rs = response(&temperature, &[0x04, 0x02, 0xFF, 0x85]);
+        assert_close(temperature.decode(&rs), 260.85);
+
+        let mut energy =
+            Point::new("energy", Table::Holding, 0, DataType::U32);
+        energy.word_order = WordOrder::Cdab;
+        energy.scale = 0.01;
+        energy.offset = -10.0;
+        // 100000
+        let rs =
+            response(&energy, &[0x03, 0x04, 0x86, 0xA0, 0x00, 0x01]);
+        assert_close(energy.decode(&rs), 990.0);
+
+        let mut flag =
+            Point::new("flag", Table::Coil, 0, DataType::Bool);
+        flag.scale = 2.0;
+        flag.offset = 1.0;
+        let rs = response(&flag, &[0x01, 0x01, 0x01]);
+        assert_close(flag.decode(&rs), 3.0);
+    }
+
+    #[test]
+    fn decode_mismatched_response() {
+        let energy =
+            Point::new("energy", Table::Holding, 0, DataType::U32);
+        let rs = response(&energy, &[0x03, 0x02, 0x00, 0x01]);
+        assert_eq!(energy.decode(&rs), None);
+        let rs = response(&energy, &[0x83, 0x02]);
+        assert_eq!(energy.decode(&rs), None);
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use crate::{Error, Exception, ExceptionResponse};
+
+/// Retry policy of the `Client`
+///
+/// Reads are retried automatically, writes only if `retry_writes` is
+/// set, as a write may have been applied although its response got
+/// lost.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts per transaction, the first one included
+    pub max_attempts:   u32,
+    /// Delay before the first retry
+    pub backoff:        Duration,
+    /// Factor the delay grows by after each retry
+    pub backoff_factor: u32,
+    /// Upper bound of the delay
+    pub max_backoff:    Duration,
+    /// Retry corrupted frames (`Error::CrcMismatch`,
+    /// `Error::LrcMismatch`)
+    pub retry_crc:      bool,
+    /// Retry response timeouts
+    pub retry_timeout:  bool,
+    /// Retry exception 0x06 Server Device Busy
+    pub retry_busy:     bool,
+    /// Retry writes as well
+    pub retry_writes:   bool
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts:   3,
+            backoff:        Duration::from_millis(100),
+            backoff_factor: 2,
+            max_backoff:    Duration::from_secs(10),
+            retry_crc:      true,
+            retry_timeout:  true,
+            retry_busy:     true,
+            retry_writes:   false
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Whether `error` belongs to a retryable class
+    pub fn is_retryable(&self, error: &Error) -> bool {
+        match error {
+            Error::CrcMismatch { .. } | Error::LrcMismatch { .. } => {
+                self.retry_crc
+            },
+            Error::Timeout => self.retry_timeout,
+            Error::Exception(Exception::ServerDeviceBusy) => {
+                self.retry_busy
+            },
+            _ => false
+        }
+    }
+
+    /// Whether the exception response is retryable
+    pub fn is_retryable_exception(
+        &self,
+        rs: &ExceptionResponse
+    ) -> bool {
+        self.retry_busy && rs.exception == Exception::ServerDeviceBusy
+    }
+
+    /// Delay before the retry following attempt `attempt` (1 based),
+    /// at most `max_backoff`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .saturating_pow(attempt.saturating_sub(1));
+        self.backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |delay| {
+                delay.min(self.max_backoff)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_by_factor() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn delay_capped_on_overflow() {
+        let policy = RetryPolicy {
+            backoff: Duration::from_secs(u64::MAX / 2),
+            backoff_factor: u32::MAX,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(1), policy.max_backoff);
+        assert_eq!(policy.delay(u32::MAX), policy.max_backoff);
+        let policy = RetryPolicy {
+            backoff_factor: 10,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.delay(40), policy.max_backoff);
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock}
+};
+
+/// Transaction id source for TCP requests
+///
+/// Ids are counted per unit id, start at 1 and wrap to 1 after
+/// 0xFFFF. The allocator can be shared across tasks.
+#[derive(Debug, Default)]
+pub struct TidAllocator {
+    tid_map: Mutex<HashMap<u8, u16>>
+}
+
+impl TidAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocator used by the `Request` constructors
+    pub fn global() -> &'static TidAllocator {
+        static GLOBAL: OnceLock<TidAllocator> = OnceLock::new();
+        GLOBAL.get_or_init(TidAllocator::new)
+    }
+
+    /// Get the next tid of `unit_id`
+    pub fn next(&self, unit_id: u8) -> u16 {
+        let mut map = self
+            .tid_map
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let value = match map.get(&unit_id) {
+            Some(v) if *v < 0xFFFF => v + 1,
+            _ => 1
+        };
+        map.insert(unit_id, value);
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_per_unit_id() {
+        let tids = TidAllocator::new();
+        assert_eq!(tids.next(0x01), 1);
+        assert_eq!(tids.next(0x01), 2);
+        assert_eq!(tids.next(0x02), 1);
+        assert_eq!(tids.next(0x01), 3);
+    }
+
+    #[test]
+    fn wraps_to_one() {
+        let tids = TidAllocator::new();
+        tids.tid_map.lock().unwrap().insert(0x01, 0xFFFE);
+        assert_eq!(tids.next(0x01), 0xFFFF);
+        assert_eq!(tids.next(0x01), 1);
+    }
+}
@@ -0,0 +1,132 @@
+//! Socket transports of the `Client`
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
+use std::{
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    task::{ready, Context, Poll}
+};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpStream, UdpSocket}
+};
+
+/// Transport which can be connected to a socket address, used by
+/// `Client::connect` to connect and reconnect
+pub trait Connect: Sized {
+    /// Whether each frame is carried by its own datagram, the client
+    /// then decodes each datagram as one frame, see
+    /// `Client::datagram`
+    const DATAGRAM: bool = false;
+
+    fn connect(
+        addr: SocketAddr
+    ) -> impl Future<Output = io::Result<Self>> + Send;
+}
+
+impl Connect for TcpStream {
+    fn connect(
+        addr: SocketAddr
+    ) -> impl Future<Output = io::Result<Self>> + Send {
+        TcpStream::connect(addr)
+    }
+}
+
+impl Connect for UdpStream {
+    const DATAGRAM: bool = true;
+
+    fn connect(
+        addr: SocketAddr
+    ) -> impl Future<Output = io::Result<Self>> + Send {
+        UdpStream::connect(addr)
+    }
+}
+
+/// Byte stream over a connected UDP socket
+///
+/// Each write is sent as one datagram, so a frame must be written at
+/// once, as `Framed` does. Each read returns one datagram, the bytes
+/// which don't fit into the read buffer are dropped. A `Client`
+/// created with `connect` or `Client::datagram` decodes each
+/// datagram as one frame.
+pub struct UdpStream {
+    socket: UdpSocket
+}
+
+impl UdpStream {
+    /// Max size of a received datagram, large enough for any modbus
+    /// frame
+    pub const MAX_DATAGRAM: usize = 1024;
+
+    /// Bind a local socket and connect it to `addr`
+    pub async fn connect(addr: SocketAddr) -> io::Result<Self> {
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(addr).await?;
+        Ok(Self::from_socket(socket))
+    }
+
+    /// Wrap a socket which is already connected
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self { socket }
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl AsyncRead for UdpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>
+    ) -> Poll<io::Result<()>> {
+        // an empty read would be taken as eof, skip empty datagrams
+        loop {
+            let mut datagram = [0u8; Self::MAX_DATAGRAM];
+            let mut datagram_buf = ReadBuf::new(&mut datagram);
+            ready!(self.socket.poll_recv(cx, &mut datagram_buf))?;
+            let datagram = datagram_buf.filled();
+            if datagram.is_empty() {
+                continue;
+            }
+            let len = datagram.len().min(buf.remaining());
+            buf.put_slice(&datagram[..len]);
+            return Poll::Ready(Ok(()));
+        }
+    }
+}
+
+impl AsyncWrite for UdpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8]
+    ) -> Poll<io::Result<usize>> {
+        self.socket.poll_send(cx, buf)
+    }
+
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,150 @@
+//! Modbus/TCP Security: MBAP frames over TLS with mutual X.509
+//! authentication
+
+use std::{io, net::SocketAddr, sync::Arc};
+
+use tokio::net::TcpStream;
+pub use tokio_rustls::{client::TlsStream, rustls};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName},
+        CertificateError, ClientConfig, RootCertStore
+    },
+    TlsConnector
+};
+use x509_parser::{
+    certificate::X509Certificate,
+    der_parser::asn1_rs::{FromDer, Utf8String}
+};
+
+use crate::{error::Result, Error};
+
+/// Registered port of Modbus/TCP Security
+pub const DEFAULT_PORT: u16 = 802;
+
+/// OID of the certificate extension carrying the Modbus role
+pub const ROLE_OID: &str = "1.3.6.1.4.1.50316.802.1";
+
+/// TLS configuration of a `TlsClient`
+///
+/// The server certificate is verified against the trusted CA
+/// certificates and `server_name`, the handshake fails if it isn't
+/// valid.
+#[derive(Clone)]
+pub struct TlsConfig {
+    config:      Arc<ClientConfig>,
+    server_name: ServerName<'static>
+}
+
+impl TlsConfig {
+    /// Use a custom rustls configuration
+    pub fn new(
+        config: Arc<ClientConfig>,
+        server_name: &str
+    ) -> Result<Self> {
+        let server_name =
+            ServerName::try_from(server_name.to_string()).map_err(
+                |e| io::Error::new(io::ErrorKind::InvalidInput, e)
+            )?;
+        Ok(Self {
+            config,
+            server_name
+        })
+    }
+
+    /// Mutual authentication from PEM files: the CA certificates
+    /// trusted to sign server certificates, the client certificate
+    /// chain and its private key
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use modbus_client::transport::tls::TlsConfig;
+    ///
+    /// # fn run() -> Result<(), modbus_client::Error> {
+    /// let config = TlsConfig::from_pem(
+    ///     &std::fs::read("ca.pem")?,
+    ///     &std::fs::read("client.pem")?,
+    ///     &std::fs::read("client.key")?,
+    ///     "plc1.local"
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_pem(
+        ca: &[u8],
+        cert: &[u8],
+        key: &[u8],
+        server_name: &str
+    ) -> Result<Self> {
+        let mut roots = RootCertStore::empty();
+        for ca in rustls_pemfile::certs(&mut &ca[..]) {
+            roots.add(ca?).map_err(Error::Tls)?;
+        }
+        let chain = rustls_pemfile::certs(&mut &cert[..])
+            .collect::<io::Result<Vec<CertificateDer<'static>>>>(
+        )?;
+        let key: PrivateKeyDer<'static> =
+            rustls_pemfile::private_key(&mut &key[..])?.ok_or_else(
+                || {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "No private key"
+                    )
+                }
+            )?;
+        let config = ClientConfig::builder_with_provider(Arc::new(
+            ring::default_provider()
+        ))
+        .with_safe_default_protocol_versions()
+        .map_err(Error::Tls)?
+        .with_root_certificates(roots)
+        .with_client_auth_cert(chain, key)
+        .map_err(Error::Tls)?;
+        Self::new(Arc::new(config), server_name)
+    }
+
+    /// Connect to `addr` and do the TLS handshake
+    pub async fn connect(
+        &self,
+        addr: SocketAddr
+    ) -> io::Result<TlsStream<TcpStream>> {
+        let stream = TcpStream::connect(addr).await?;
+        TlsConnector::from(self.config.clone())
+            .connect(self.server_name.clone(), stream)
+            .await
+    }
+}
+
+/// Modbus role of the peer, from the role extension of its end
+/// entity certificate. `None` if the certificate has no role.
+pub fn peer_role(
+    stream: &TlsStream<TcpStream>
+) -> Result<Option<String>> {
+    let bad_encoding = || {
+        Error::Tls(rustls::Error::InvalidCertificate(
+            CertificateError::BadEncoding
+        ))
+    };
+    let Some(cert) = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+    else {
+        return Ok(None);
+    };
+    let (_, cert) = X509Certificate::from_der(cert)
+        .map_err(|_| bad_encoding())?;
+    let Some(extension) = cert
+        .extensions()
+        .iter()
+        .find(|ext| ext.oid.to_id_string() == ROLE_OID)
+    else {
+        return Ok(None);
+    };
+    let (_, role) = Utf8String::from_der(extension.value)
+        .map_err(|_| bad_encoding())?;
+    Ok(Some(role.string()))
+}
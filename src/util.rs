@@ -0,0 +1,46 @@
+//! Conversions between the byte buffers of the modbus bodies and
+//! typed values
+
+/// Unpack coil/discrete input bytes (LSB first) into `quantity` bits
+pub(crate) fn bytes_to_bits(
+    bytes: &[u8],
+    quantity: u16
+) -> Vec<bool> {
+    bytes
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| byte & (1 << i) != 0))
+        .take(quantity as usize)
+        .collect()
+}
+
+/// Pack bits into coil bytes, LSB first
+pub(crate) fn bits_to_bytes(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0u8, |byte, (i, bit)| {
+                byte | ((*bit as u8) << i)
+            })
+        })
+        .collect()
+}
+
+/// Big-endian register bytes into registers
+pub fn bytes_to_registers(bytes: &[u8]) -> Vec<u16> {
+    bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+/// Registers into big-endian register bytes
+pub fn registers_to_bytes(registers: &[u16]) -> Vec<u8> {
+    registers.iter().flat_map(|reg| reg.to_be_bytes()).collect()
+}
+
+/// Longitudinal redundancy check of modbus ASCII: two's complement of
+/// the sum of the bytes
+pub(crate) fn lrc(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+        .wrapping_neg()
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "derive")]
+use bytes::BytesMut;
+use modbus_client::{codec::TcpCodec, ModbusRegisters};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[derive(Debug, PartialEq, ModbusRegisters)]
+#[modbus(word_order = "CDAB")]
+struct Drive {
+    speed:       f32,
+    #[modbus(ty = "i16", scale = 0.1)]
+    temperature: f64,
+    #[modbus(offset = 4)]
+    status:      u16,
+    #[modbus(offset = 6, word_order = "ABCD")]
+    energy:      u32
+}
+
+const DRIVE: [u8; 16] = [
+    0x00, 0x00, 0x3F, 0xC0, 0xFF, 0x85, 0x00, 0x00, 0x00, 0x01, 0x00,
+    0x00, 0x00, 0x01, 0x00, 0x02
+];
+
+fn drive() -> Drive {
+    Drive {
+        speed:       1.5,
+        temperature: -12.3,
+        status:      0x0001,
+        energy:      0x0001_0002
+    }
+}
+
+#[test]
+fn write_request() {
+    let mut codec = TcpCodec::default();
+    let mut dst = BytesMut::new();
+    codec
+        .encode(drive().write_request(0x01, 0x0100), &mut dst)
+        .unwrap();
+    assert_eq!(
+        &dst[..13],
+        [
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x17, 0x01, 0x10, 0x01,
+            0x00, 0x00, 0x08, 0x10
+        ]
+    );
+    assert_eq!(&dst[13..], DRIVE);
+}
+
+#[test]
+fn from_response() {
+    let mut codec = TcpCodec::default();
+    let mut dst = BytesMut::new();
+    codec
+        .encode(Drive::read_request(0x01, 0x0100), &mut dst)
+        .unwrap();
+    assert_eq!(
+        &dst[..],
+        [
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x01,
+            0x00, 0x00, 0x08
+        ]
+    );
+    let mut src = BytesMut::from(
+        &[0x00, 0x01, 0x00, 0x00, 0x00, 0x13, 0x01, 0x03, 0x10][..]
+    );
+    src.extend_from_slice(&DRIVE);
+    let response = codec.decode(&mut src).unwrap().unwrap();
+    assert_eq!(Drive::from_response(&response), Some(drive()));
+}
+
+#[test]
+fn from_short_response() {
+    let mut codec = TcpCodec::default();
+    let mut dst = BytesMut::new();
+    codec
+        .encode(Drive::read_request(0x01, 0x0100), &mut dst)
+        .unwrap();
+    let mut src = BytesMut::from(
+        &[0x00, 0x01, 0x00, 0x00, 0x00, 0x11, 0x01, 0x03, 0x0E][..]
+    );
+    src.extend_from_slice(&DRIVE[..14]);
+    let response = codec.decode(&mut src).unwrap().unwrap();
+    assert_eq!(Drive::from_response(&response), None);
+}
+
+#[test]
+fn invalid_attributes() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
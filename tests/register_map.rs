@@ -0,0 +1,145 @@
+#![cfg(any(feature = "toml", feature = "json", feature = "csv"))]
+
+use modbus_client::{
+    Access, DataType, Error, RegisterMap, Table, WordOrder
+};
+
+/// Checks the points of the maps below, `frequency` leaves out the
+/// values with defaults
+fn check(map: &RegisterMap) {
+    let power = map.get("active_power").unwrap();
+    assert_eq!(power.table, Table::Holding);
+    assert_eq!(power.address, 0x0010);
+    assert_eq!(power.data_type, DataType::F32);
+    assert_eq!(power.word_order, WordOrder::Cdab);
+    assert_eq!(power.scale, 0.1);
+    assert_eq!(power.offset, -40.0);
+    assert_eq!(power.unit.as_deref(), Some("kW"));
+    assert_eq!(power.access, Access::ReadWrite);
+    let frequency = map.get("frequency").unwrap();
+    assert_eq!(frequency.table, Table::Input);
+    assert_eq!(frequency.address, 0x0020);
+    assert_eq!(frequency.data_type, DataType::U16);
+    assert_eq!(frequency.word_order, WordOrder::Abcd);
+    assert_eq!(frequency.scale, 1.0);
+    assert_eq!(frequency.offset, 0.0);
+    assert_eq!(frequency.unit, None);
+    assert_eq!(frequency.access, Access::Read);
+    assert!(map.get("voltage").is_none());
+}
+
+fn overlap(rs: Result<RegisterMap, Error>) {
+    assert!(
+        matches!(&rs, Err(Error::InvalidPoint { name, reason }) if name == "frequency" && reason.contains("overlaps")),
+        "{:?}",
+        rs
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn from_toml() {
+    let map = RegisterMap::from_toml(
+        r#"
+        [[points]]
+        name = "active_power"
+        table = "holding"
+        address = 0x0010
+        data_type = "f32"
+        word_order = "CDAB"
+        scale = 0.1
+        offset = -40.0
+        unit = "kW"
+        access = "read_write"
+
+        [[points]]
+        name = "frequency"
+        table = "input"
+        address = 0x0020
+        data_type = "u16"
+        "#
+    )
+    .unwrap();
+    check(&map);
+    overlap(RegisterMap::from_toml(
+        r#"
+        [[points]]
+        name = "active_power"
+        table = "holding"
+        address = 0x0010
+        data_type = "f32"
+
+        [[points]]
+        name = "frequency"
+        table = "holding"
+        address = 0x0011
+        data_type = "u16"
+        "#
+    ));
+    // address is required
+    let rs = RegisterMap::from_toml(
+        r#"
+        [[points]]
+        name = "frequency"
+        table = "input"
+        data_type = "u16"
+        "#
+    );
+    assert!(matches!(rs, Err(Error::InvalidMap(_))));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn from_json() {
+    let map = RegisterMap::from_json(
+        r#"{"points": [
+            {"name": "active_power", "table": "holding", "address": 16, "data_type": "f32",
+             "word_order": "CDAB", "scale": 0.1, "offset": -40.0, "unit": "kW",
+             "access": "read_write"},
+            {"name": "frequency", "table": "input", "address": 32, "data_type": "u16"}
+        ]}"#
+    )
+    .unwrap();
+    check(&map);
+    overlap(RegisterMap::from_json(
+        r#"{"points": [
+            {"name": "active_power", "table": "holding", "address": 16, "data_type": "f32"},
+            {"name": "frequency", "table": "holding", "address": 17, "data_type": "u16"}
+        ]}"#
+    ));
+    let rs = RegisterMap::from_json(
+        r#"{"points": [{"name": "frequency"}]}"#
+    );
+    assert!(matches!(rs, Err(Error::InvalidMap(_))));
+}
+
+/// CSV file of the header and `rows`
+#[cfg(feature = "csv")]
+fn csv(rows: &[&str]) -> String {
+    let mut csv = "name,table,address,data_type,word_order,scale,\
+                   offset,unit,access\n"
+        .to_string();
+    for row in rows {
+        csv.push_str(row);
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn from_csv() {
+    let map = RegisterMap::from_csv(&csv(&[
+        "active_power,holding,16,f32,CDAB,0.1,-40,kW,read_write",
+        "frequency,input,32,u16,,,,,"
+    ]))
+    .unwrap();
+    check(&map);
+    overlap(RegisterMap::from_csv(&csv(&[
+        "active_power,holding,16,f32,,,,,",
+        "frequency,holding,17,u16,,,,,"
+    ])));
+    let rs =
+        RegisterMap::from_csv(&csv(&["frequency,input,x,u16,,,,,"]));
+    assert!(matches!(rs, Err(Error::InvalidMap(_))));
+}
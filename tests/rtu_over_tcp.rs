@@ -0,0 +1,43 @@
+use easy_modbus::util::crc;
+use modbus_client::{Error, RtuOverTcpClient};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener
+};
+
+fn with_crc(frame: &[u8]) -> Vec<u8> {
+    let crc = crc::compute(frame);
+    [frame, &crc.to_be_bytes()].concat()
+}
+
+#[tokio::test]
+async fn reconnect_after_close() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        // the converter drops the first connection
+        let (first, _) = listener.accept().await.unwrap();
+        drop(first);
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 8];
+        stream.read_exact(&mut request).await.unwrap();
+        assert_eq!(
+            request[..],
+            with_crc(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01])
+        );
+        stream
+            .write_all(&with_crc(&[0x01, 0x03, 0x02, 0x00, 0x05]))
+            .await
+            .unwrap();
+    });
+    let mut client = RtuOverTcpClient::connect(addr).await.unwrap();
+    let rs = client.read_holding_registers(0x01, 0x0000, 1).await;
+    assert!(matches!(rs, Err(Error::Io(_))), "{:?}", rs);
+    // reconnected before the next request
+    let registers = client
+        .read_holding_registers(0x01, 0x0000, 1)
+        .await
+        .unwrap();
+    assert_eq!(registers, vec![0x0005]);
+    server.await.unwrap();
+}
@@ -0,0 +1,226 @@
+#![cfg(feature = "tls")]
+
+use std::{net::SocketAddr, sync::Arc};
+
+use modbus_client::{transport::tls::TlsConfig, Error, TlsClient};
+use rcgen::{CertificateParams, CustomExtension, KeyPair};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{
+            CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer
+        },
+        server::WebPkiClientVerifier,
+        CertificateError, RootCertStore, ServerConfig
+    },
+    TlsAcceptor
+};
+
+/// Self-signed certificate and its private key
+struct Identity {
+    cert:     CertificateDer<'static>,
+    cert_pem: String,
+    key_pem:  String,
+    key:      PrivateKeyDer<'static>
+}
+
+fn identity(name: &str, role: Option<&str>) -> Identity {
+    let key = KeyPair::generate().unwrap();
+    let mut params =
+        CertificateParams::new(vec![name.to_string()]).unwrap();
+    if let Some(role) = role {
+        // UTF8String of the role
+        let mut content = vec![0x0C, role.len() as u8];
+        content.extend_from_slice(role.as_bytes());
+        params.custom_extensions.push(
+            CustomExtension::from_oid_content(
+                &[1, 3, 6, 1, 4, 1, 50316, 802, 1],
+                content
+            )
+        );
+    }
+    let cert = params.self_signed(&key).unwrap();
+    Identity {
+        cert:     cert.der().clone(),
+        cert_pem: cert.pem(),
+        key_pem:  key.serialize_pem(),
+        key:      PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            key.serialize_der()
+        ))
+    }
+}
+
+/// Server requiring a client certificate signed by `client`, which
+/// answers each read holding registers request with `[1, 2]`
+///
+/// With `corrupt` the first session answers with a record which
+/// can't be decrypted and is closed.
+async fn server(
+    server: &Identity,
+    client: &Identity,
+    corrupt: bool
+) -> SocketAddr {
+    let provider = Arc::new(ring::default_provider());
+    let mut roots = RootCertStore::empty();
+    roots.add(client.cert.clone()).unwrap();
+    let verifier = WebPkiClientVerifier::builder_with_provider(
+        Arc::new(roots),
+        provider.clone()
+    )
+    .build()
+    .unwrap();
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(
+            vec![server.cert.clone()],
+            server.key.clone_key()
+        )
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut corrupt = corrupt;
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let Ok(mut stream) = acceptor.accept(stream).await else {
+                continue;
+            };
+            let mut request = [0u8; 12];
+            if std::mem::take(&mut corrupt) {
+                stream.read_exact(&mut request).await.unwrap();
+                // application data record of garbage
+                let (tcp, _) = stream.get_mut();
+                tcp.write_all(&[
+                    0x17, 0x03, 0x03, 0x00, 0x05, 1, 2, 3, 4, 5
+                ])
+                .await
+                .unwrap();
+                continue;
+            }
+            while stream.read_exact(&mut request).await.is_ok() {
+                let response = [
+                    request[0], request[1], 0x00, 0x00, 0x00, 0x07,
+                    request[6], 0x03, 0x04, 0x00, 0x01, 0x00, 0x02
+                ];
+                stream.write_all(&response).await.unwrap();
+            }
+        }
+    });
+    addr
+}
+
+fn config(
+    trusted: &Identity,
+    client: &Identity,
+    server_name: &str
+) -> TlsConfig {
+    TlsConfig::from_pem(
+        trusted.cert_pem.as_bytes(),
+        client.cert_pem.as_bytes(),
+        client.key_pem.as_bytes(),
+        server_name
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn mutual_authentication() {
+    let plc = identity("localhost", Some("operator"));
+    let client = identity("client", None);
+    let addr = server(&plc, &client, false).await;
+    let mut tls = TlsClient::connect_tls(
+        addr,
+        config(&plc, &client, "localhost")
+    )
+    .await
+    .unwrap();
+    assert_eq!(tls.peer_role().unwrap().as_deref(), Some("operator"));
+    assert_eq!(
+        tls.read_holding_registers(0x01, 0x0000, 2).await.unwrap(),
+        vec![1, 2]
+    );
+}
+
+#[tokio::test]
+async fn peer_without_role() {
+    let plc = identity("localhost", None);
+    let client = identity("client", None);
+    let addr = server(&plc, &client, false).await;
+    let tls = TlsClient::connect_tls(
+        addr,
+        config(&plc, &client, "localhost")
+    )
+    .await
+    .unwrap();
+    assert_eq!(tls.peer_role().unwrap(), None);
+}
+
+#[tokio::test]
+async fn untrusted_server_certificate() {
+    let plc = identity("localhost", Some("operator"));
+    let client = identity("client", None);
+    let addr = server(&plc, &client, false).await;
+    // trusts another self-signed certificate of the same name
+    let other = identity("localhost", Some("operator"));
+    let rs = TlsClient::connect_tls(
+        addr,
+        config(&other, &client, "localhost")
+    )
+    .await;
+    assert!(
+        matches!(
+            rs,
+            Err(Error::Tls(
+                tokio_rustls::rustls::Error::InvalidCertificate(
+                    CertificateError::BadSignature
+                        | CertificateError::UnknownIssuer
+                )
+            ))
+        ),
+        "{:?}",
+        rs.err()
+    );
+    // certificate not valid for the server name
+    let rs = TlsClient::connect_tls(
+        addr,
+        config(&plc, &client, "plc.example")
+    )
+    .await;
+    assert!(
+        matches!(
+            rs,
+            Err(Error::Tls(
+                tokio_rustls::rustls::Error::InvalidCertificate(_)
+            ))
+        ),
+        "{:?}",
+        rs.err()
+    );
+}
+
+#[tokio::test]
+async fn reconnect_after_tls_error() {
+    let plc = identity("localhost", None);
+    let client = identity("client", None);
+    let addr = server(&plc, &client, true).await;
+    let mut tls = TlsClient::connect_tls(
+        addr,
+        config(&plc, &client, "localhost")
+    )
+    .await
+    .unwrap();
+    let rs = tls.read_holding_registers(0x01, 0x0000, 2).await;
+    assert!(matches!(rs, Err(Error::Tls(_))), "{:?}", rs);
+    // the broken session is replaced by a new one
+    assert_eq!(
+        tls.read_holding_registers(0x01, 0x0000, 2).await.unwrap(),
+        vec![1, 2]
+    );
+}
@@ -0,0 +1,116 @@
+use modbus_client::{
+    codec::TcpCodec, transport::UdpStream, Client, UdpClient
+};
+use tokio::net::UdpSocket;
+
+/// Client over a `UdpStream` created with `Client::datagram` and the
+/// server socket it's connected to
+async fn udp() -> (Client<UdpStream, TcpCodec>, UdpSocket) {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.connect(server.local_addr().unwrap()).await.unwrap();
+    server.connect(socket.local_addr().unwrap()).await.unwrap();
+    let client = Client::datagram(
+        UdpStream::from_socket(socket),
+        TcpCodec::default()
+    );
+    (client, server)
+}
+
+/// Receive a read holding registers request and return its tid
+async fn recv_request(server: &UdpSocket) -> [u8; 2] {
+    let mut buf = [0u8; 64];
+    let len = server.recv(&mut buf).await.unwrap();
+    assert_eq!(len, 12);
+    [buf[0], buf[1]]
+}
+
+#[tokio::test]
+async fn truncated_datagram_dropped() {
+    let (mut client, server) = udp().await;
+    let task = tokio::spawn(async move {
+        let [high, low] = recv_request(&server).await;
+        // truncated frame, its rest must not be taken from the next
+        // datagram
+        server
+            .send(&[
+                high, low, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04,
+                0x00
+            ])
+            .await
+            .unwrap();
+        server
+            .send(&[
+                high, low, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04,
+                0x00, 0x0A, 0x00, 0x0B
+            ])
+            .await
+            .unwrap();
+    });
+    let registers = client
+        .read_holding_registers(0x01, 0x0000, 2)
+        .await
+        .unwrap();
+    assert_eq!(registers, vec![0x000A, 0x000B]);
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn oversized_datagram_rest_dropped() {
+    let (mut client, server) = udp().await;
+    let task = tokio::spawn(async move {
+        for value in [0x000A, 0x000B] {
+            let [high, low] = recv_request(&server).await;
+            let [v_high, v_low] = u16::to_be_bytes(value);
+            // a frame followed by the start of another one
+            server
+                .send(&[
+                    high, low, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03,
+                    0x02, v_high, v_low, high, low, 0x00, 0x00
+                ])
+                .await
+                .unwrap();
+        }
+    });
+    for value in [0x000A, 0x000B] {
+        let registers = client
+            .read_holding_registers(0x01, 0x0000, 1)
+            .await
+            .unwrap();
+        assert_eq!(registers, vec![value]);
+    }
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn connect_reads_datagrams() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut client = UdpClient::connect(server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let task = tokio::spawn(async move {
+        let mut buf = [0u8; 64];
+        let (_, peer) = server.recv_from(&mut buf).await.unwrap();
+        let [high, low] = [buf[0], buf[1]];
+        server
+            .send_to(&[high, low, 0x00, 0x00, 0x00, 0x05, 0x01], peer)
+            .await
+            .unwrap();
+        server
+            .send_to(
+                &[
+                    high, low, 0x00, 0x00, 0x00, 0x05, 0x01, 0x03,
+                    0x02, 0x00, 0x0A
+                ],
+                peer
+            )
+            .await
+            .unwrap();
+    });
+    let registers = client
+        .read_holding_registers(0x01, 0x0000, 1)
+        .await
+        .unwrap();
+    assert_eq!(registers, vec![0x000A]);
+    task.await.unwrap();
+}
@@ -0,0 +1,50 @@
+use modbus_client::ModbusRegisters;
+
+#[derive(ModbusRegisters)]
+struct UnknownOption {
+    #[modbus(offest = 1)]
+    value: u16
+}
+
+#[derive(ModbusRegisters)]
+struct WordOrder {
+    #[modbus(word_order = "ACBD")]
+    value: u32
+}
+
+#[derive(ModbusRegisters)]
+#[modbus(offset = 1)]
+struct StructOffset {
+    value: u16
+}
+
+#[derive(ModbusRegisters)]
+struct ZeroScale {
+    #[modbus(ty = "i16", scale = 0.0)]
+    value: f64
+}
+
+#[derive(ModbusRegisters)]
+struct Overlap {
+    #[modbus(offset = 2)]
+    low:  u32,
+    #[modbus(offset = 3)]
+    high: u16
+}
+
+#[derive(ModbusRegisters)]
+struct OverlapNext {
+    first:  u32,
+    #[modbus(offset = 0)]
+    second: u16
+}
+
+#[derive(ModbusRegisters)]
+struct Tuple(u16);
+
+#[derive(ModbusRegisters)]
+enum Enum {
+    Value
+}
+
+fn main() {}